@@ -0,0 +1,188 @@
+use anyhow::{anyhow, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::ffi::OsString;
+use std::fmt::Display;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Format of the archive that packages the output directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Zip,
+    TarGz,
+}
+
+impl ArchiveFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Zip => "zip",
+            Self::TarGz => "tar.gz",
+        }
+    }
+
+    /// Returns the archive path for `output_dir` (e.g. `out/gde-xxx` -> `out/gde-xxx.zip`)
+    pub fn archive_path(&self, output_dir: impl AsRef<Path>) -> PathBuf {
+        let mut path = OsString::from(output_dir.as_ref().as_os_str());
+        path.push(".");
+        path.push(self.extension());
+        path.into()
+    }
+}
+
+impl FromStr for ArchiveFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "zip" => Ok(Self::Zip),
+            "tar.gz" | "tgz" => Ok(Self::TarGz),
+            _ => Err(format!(
+                "unknown archive format \"{s}\" (expected zip or tar.gz)"
+            )),
+        }
+    }
+}
+
+impl Display for ArchiveFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.extension())
+    }
+}
+
+/// Packs every file under `source_dir` into `dest_file`.
+/// Entries are sorted by path and stamped with `mtime(path)` (a unix timestamp) instead of the
+/// file system time, so packaging the same commits twice produces byte-identical archives.
+/// Modes are normalized to 0755 for executable files and 0644 for all others.
+pub fn write_archive(
+    format: ArchiveFormat,
+    source_dir: impl AsRef<Path>,
+    dest_file: impl AsRef<Path>,
+    mtime: impl Fn(&str) -> i64,
+) -> Result<()> {
+    let source_dir = source_dir.as_ref();
+    let files = crate::list_files(source_dir)?;
+
+    let out = BufWriter::new(File::create(dest_file.as_ref())?);
+    match format {
+        ArchiveFormat::Zip => write_zip(source_dir, &files, out, mtime),
+        ArchiveFormat::TarGz => write_tar_gz(source_dir, &files, out, mtime),
+    }
+}
+
+fn write_zip<W: Write + std::io::Seek>(
+    source_dir: &Path,
+    files: &[String],
+    out: W,
+    mtime: impl Fn(&str) -> i64,
+) -> Result<()> {
+    let mut zip = ZipWriter::new(out);
+    for file in files.iter() {
+        let options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .last_modified_time(zip_datetime(mtime(file))?)
+            .unix_permissions(entry_mode(&source_dir.join(file))?);
+        zip.start_file(file, options)?;
+        zip.write_all(&fs::read(source_dir.join(file))?)?;
+    }
+    zip.finish()?.flush()?;
+    Ok(())
+}
+
+fn write_tar_gz<W: Write>(
+    source_dir: &Path,
+    files: &[String],
+    out: W,
+    mtime: impl Fn(&str) -> i64,
+) -> Result<()> {
+    let mut tar = tar::Builder::new(GzEncoder::new(out, Compression::default()));
+    for file in files.iter() {
+        let data = fs::read(source_dir.join(file))?;
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Regular);
+        header.set_size(data.len() as u64);
+        header.set_mode(entry_mode(&source_dir.join(file))?);
+        header.set_mtime(mtime(file).max(0) as u64);
+        header.set_uid(0);
+        header.set_gid(0);
+        tar.append_data(&mut header, file, data.as_slice())?;
+    }
+    tar.into_inner()?.finish()?.flush()?;
+    Ok(())
+}
+
+/// Returns 0755 if the staged file is executable and 0644 otherwise
+#[cfg(unix)]
+fn entry_mode(path: &Path) -> Result<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = fs::metadata(path)?.permissions().mode();
+    Ok(if mode & 0o111 != 0 { 0o755 } else { 0o644 })
+}
+
+#[cfg(not(unix))]
+fn entry_mode(_path: &Path) -> Result<u32> {
+    Ok(0o644)
+}
+
+/// Converts a unix timestamp (UTC) into a zip timestamp, clamped to the range zip can store
+fn zip_datetime(timestamp: i64) -> Result<zip::DateTime> {
+    let days = timestamp.div_euclid(86_400);
+    let secs = timestamp.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+    if year < 1980 {
+        return Ok(zip::DateTime::default());
+    }
+    let year = year.min(2107) as u16;
+    zip::DateTime::from_date_and_time(
+        year,
+        month,
+        day,
+        (secs / 3600) as u8,
+        (secs % 3600 / 60) as u8,
+        (secs % 60) as u8,
+    )
+    .map_err(|_| anyhow!("Failed to convert timestamp {timestamp}"))
+}
+
+/// Converts days since 1970-01-01 into (year, month, day)
+/// See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn civil_from_days_converts_known_dates() {
+        assert_eq!((1970, 1, 1), civil_from_days(0));
+        assert_eq!((2000, 2, 29), civil_from_days(11_016));
+        assert_eq!((2024, 1, 10), civil_from_days(19_732));
+    }
+
+    #[test]
+    fn archive_format_parses_and_builds_paths() {
+        assert_eq!(Ok(ArchiveFormat::Zip), "zip".parse());
+        assert_eq!(Ok(ArchiveFormat::TarGz), "tar.gz".parse());
+        assert_eq!(Ok(ArchiveFormat::TarGz), "tgz".parse());
+        assert!("rar".parse::<ArchiveFormat>().is_err());
+        assert_eq!(
+            PathBuf::from("out/gde-1.tar.gz"),
+            ArchiveFormat::TarGz.archive_path("out/gde-1")
+        );
+    }
+}
@@ -1,9 +1,54 @@
-use crate::git::{Git, GitLocalBranch, GitLocalBranches, GitMergeBase, GitRevision};
-use crate::FilesCopy;
-use anyhow::Result;
+use crate::event::{Event, Observer};
+use crate::git::{Git, GitLocalBranch, GitLocalBranches, GitMergeBase, GitRevision, RefSource};
+use crate::plan::{AutoPlan, BranchCandidate, BranchPlan, SkippedBranch};
+use crate::report::{AutoReport, BranchReport, CopyReport, Stopwatch};
+use crate::{CopyOptions, FilesCopy};
+use anyhow::{anyhow, Context, Result};
+use glob::{MatchOptions, Pattern};
+use regex::Regex;
 use std::collections::{HashMap, HashSet};
-use std::io::Write;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+/// What the workers of [`AutoCopy::run`] send to the thread that owns the observer
+enum Message {
+    Event(Event),
+    Done(usize, crate::Result<CopyReport>),
+}
+
+/// Forwards the events of a branch's copy to the observer's thread
+struct ChannelObserver<'a> {
+    name: &'a str,
+    tx: &'a mpsc::Sender<Message>,
+}
+
+impl ChannelObserver<'_> {
+    fn send(&self, event: Event) -> Result<()> {
+        self.tx
+            .send(Message::Event(event))
+            .map_err(|_| anyhow!("The observer of branch {} has gone away", self.name))
+    }
+}
+
+impl Observer for ChannelObserver<'_> {
+    fn notify(&mut self, event: Event) -> Result<()> {
+        self.send(Event::Branch {
+            name: self.name.to_string(),
+            event: Box::new(event),
+        })
+    }
+}
+
+/// The outcome of the branch selection
+struct Selection {
+    git_version: String,
+    from_hash: String,
+    skipped: Vec<SkippedBranch>,
+    tasks: Vec<(GitLocalBranch, FilesCopy)>,
+    candidates: Vec<BranchCandidate>,
+}
 
 pub struct AutoCopy {
     git_path: PathBuf,
@@ -11,8 +56,13 @@ pub struct AutoCopy {
     days: u64,
     target_dir: PathBuf,
     output_dir: PathBuf,
-    excludes: HashSet<String>,
+    includes: Vec<String>,
+    excludes: Vec<String>,
     output_with_short_hash: bool,
+    options: CopyOptions,
+    jobs: usize,
+    ref_sources: Vec<RefSource>,
+    branches: Option<Vec<String>>,
 }
 
 impl AutoCopy {
@@ -31,12 +81,203 @@ impl AutoCopy {
             days,
             target_dir: target_dir.into(),
             output_dir: output_dir.into(),
+            includes: Vec::new(),
             excludes: excludes.into_iter().collect(),
             output_with_short_hash,
+            options: CopyOptions::default(),
+            jobs: 1,
+            ref_sources: vec![RefSource::Local],
+            branches: None,
+        }
+    }
+
+    /// Sets the optional outputs written for every processed branch
+    pub fn with_options(mut self, options: CopyOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Only processes branches matching one of these patterns. Patterns are globs, or regular
+    /// expressions when prefixed with `re:`; both must match the whole branch name.
+    pub fn with_includes(mut self, includes: impl IntoIterator<Item = String>) -> Self {
+        self.includes = includes.into_iter().collect();
+        self
+    }
+
+    /// Sets how many branches are processed at the same time
+    pub fn with_jobs(mut self, jobs: usize) -> Self {
+        self.jobs = jobs.max(1);
+        self
+    }
+
+    /// Sets which refs are branch candidates. A remote branch pointing to the same commit as an
+    /// already listed branch of the same name is skipped.
+    pub fn with_ref_sources(mut self, sources: impl IntoIterator<Item = RefSource>) -> Self {
+        let sources = sources.into_iter().collect::<Vec<_>>();
+        if !sources.is_empty() {
+            self.ref_sources = sources;
         }
+        self
+    }
+
+    /// Processes exactly these candidate branches instead of the ones the selection picks, such as
+    /// branches the user picked from [`AutoCopy::selection`]
+    pub fn with_branches(mut self, names: impl IntoIterator<Item = String>) -> Self {
+        self.branches = Some(names.into_iter().collect());
+        self
     }
 
-    pub fn copy<W: Write>(&self, w: &mut W) -> Result<()> {
+    /// Processes every selected branch and returns a record of the run, which is also written to
+    /// `report.json` in the output directory. A failing branch does not stop the others; the
+    /// failures are listed in the summary and in the report.
+    pub fn run<O: Observer + ?Sized>(&self, observer: &mut O) -> crate::Result<AutoReport> {
+        let stopwatch = Stopwatch::start();
+        let selection = self.select()?;
+        crate::create_output_dir(&self.output_dir)?;
+        observer.notify(Event::AutoStarted {
+            base: selection.from_hash.clone(),
+            days: self.days,
+            output_dir: self.output_dir.clone(),
+        })?;
+        for branch in selection.skipped.iter() {
+            observer.notify(Event::BranchSkipped {
+                name: branch.name.clone(),
+                reason: branch.reason.clone(),
+            })?;
+        }
+        observer.notify(Event::BranchesSelected {
+            count: selection.tasks.len(),
+        })?;
+        let tasks = selection.tasks;
+
+        // The workers send their events to this thread, which owns the observer
+        let next = AtomicUsize::new(0);
+        let (tx, rx) = mpsc::channel();
+        let mut branches = vec![None; tasks.len()];
+        thread::scope(|scope| -> Result<()> {
+            for _ in 0..self.jobs.min(tasks.len()) {
+                let (tasks, next, tx) = (&tasks, &next, tx.clone());
+                scope.spawn(move || loop {
+                    let index = next.fetch_add(1, Ordering::SeqCst);
+                    let Some((branch, copy)) = tasks.get(index) else {
+                        break;
+                    };
+                    let mut observer = ChannelObserver {
+                        name: &branch.name,
+                        tx: &tx,
+                    };
+                    let ret = observer
+                        .send(Event::BranchStarted {
+                            name: branch.name.clone(),
+                            output_dir: copy.output_dir().to_path_buf(),
+                        })
+                        .map_err(Into::into)
+                        .and_then(|_| copy.copy(&mut observer));
+                    if tx.send(Message::Done(index, ret)).is_err() {
+                        break;
+                    }
+                });
+            }
+            drop(tx);
+
+            for message in rx {
+                let (index, ret) = match message {
+                    Message::Event(event) => {
+                        observer.notify(event)?;
+                        continue;
+                    }
+                    Message::Done(index, ret) => (index, ret),
+                };
+                let (branch, copy) = &tasks[index];
+                let (error, report) = match ret {
+                    Ok(report) => (None, Some(report)),
+                    Err(e) => (Some(format!("{e:#}")), None),
+                };
+                observer.notify(Event::BranchFinished {
+                    name: branch.name.clone(),
+                    error: error.clone(),
+                })?;
+                branches[index] = Some(BranchReport {
+                    name: branch.name.clone(),
+                    head: branch.head_hash.clone(),
+                    output_dir: copy.output_dir().to_path_buf(),
+                    error,
+                    report,
+                });
+            }
+            Ok(())
+        })?;
+
+        let mut report = AutoReport {
+            git_version: selection.git_version,
+            base: selection.from_hash,
+            output_dir: self.output_dir.clone(),
+            started_at: stopwatch.started_at(),
+            duration_ms: 0,
+            branches: branches.into_iter().flatten().collect(),
+            skipped: selection.skipped,
+        };
+        let failed = report
+            .failed()
+            .iter()
+            .map(|x| x.name.clone())
+            .collect::<Vec<_>>();
+        observer.notify(Event::BranchesFinished {
+            succeeded: report.branches.len() - failed.len(),
+            failed,
+        })?;
+        report.duration_ms = stopwatch.elapsed_ms();
+        observer.notify(Event::FileWritten {
+            path: report.write()?,
+        })?;
+        observer.notify(Event::Finished {
+            duration_ms: report.duration_ms,
+        })?;
+
+        Ok(report)
+    }
+
+    /// Like [`AutoCopy::run`], but returns an error if any branch failed
+    pub fn copy<O: Observer + ?Sized>(&self, observer: &mut O) -> crate::Result<()> {
+        let report = self.run(observer)?;
+        let failed = report.failed().len();
+        if failed > 0 {
+            return Err(anyhow!("{} of {} branches failed", failed, report.branches.len()).into());
+        }
+
+        Ok(())
+    }
+
+    /// Runs the branch selection and output directory resolution of [`AutoCopy::copy`] and
+    /// returns every file it would write, without touching the filesystem
+    pub fn plan(&self) -> crate::Result<AutoPlan> {
+        let selection = self.select()?;
+        let branches = selection
+            .tasks
+            .into_iter()
+            .map(|(branch, copy)| {
+                Ok(BranchPlan {
+                    name: branch.name,
+                    head: branch.head_hash,
+                    plan: copy.plan()?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(AutoPlan {
+            base: selection.from_hash,
+            branches,
+            skipped: selection.skipped,
+        })
+    }
+
+    /// Lists the candidate branches with the reason the selection skips each one and the output
+    /// directory of the ones that would be processed, without touching the filesystem
+    pub fn selection(&self) -> crate::Result<Vec<BranchCandidate>> {
+        Ok(self.select()?.candidates)
+    }
+
+    /// Lists the candidate branches and builds a copy for every selected one
+    fn select(&self) -> Result<Selection> {
         let git = Git::from_path(&self.git_path)?;
         let root_dir = git.get_rootdir(&self.target_dir)?;
         let from_hash = git.get_hash(&root_dir, &self.from_commit)?;
@@ -46,67 +287,141 @@ impl AutoCopy {
         let merge_base = GitMergeBase::new(&self.git_path, &root_dir)?;
         let from_timestamp = revision.commit_timestamp(&from_hash)?;
         let max_delta = self.days.saturating_mul(86_400);
+        let includes = BranchPattern::parse_all(&self.includes)?;
+        let excludes = BranchPattern::parse_all(&self.excludes)?;
 
-        writeln!(w, "Base commit: {from_hash}")?;
-        writeln!(w, "Days: {}", self.days)?;
-        writeln!(w, "Output directory: {}", self.output_dir.display())?;
-
+        let mut skipped = Vec::new();
         let mut selected = Vec::new();
-        for branch in branches.list()? {
-            match self.selection_reason(
+        let mut reasons = Vec::new();
+        for branch in self.list_branches(&branches, &mut skipped)? {
+            let reason = self.selection_reason(
                 &branch,
+                (&includes, &excludes),
                 &from_hash,
                 from_timestamp,
                 max_delta,
                 &merge_base,
-            )? {
-                Some(reason) => {
-                    writeln!(w, "Skipped branch: {} ({reason})", branch.name)?;
-                }
-                None => selected.push(branch),
+            )?;
+            let chosen = match self.branches.as_ref() {
+                Some(names) => names.contains(&branch.name),
+                None => reason.is_none(),
+            };
+            if chosen {
+                selected.push(branch.clone());
+            } else {
+                skipped.push(SkippedBranch {
+                    name: branch.name.clone(),
+                    reason: reason.clone().unwrap_or_else(|| "not chosen".to_string()),
+                });
             }
+            reasons.push((branch, reason));
         }
 
-        let output_dirs = self.resolve_output_dirs(&selected, &revision)?;
-        writeln!(w, "Selected branch count: {}", selected.len())?;
-
-        for branch in selected {
-            let branch_output_dir = output_dirs
-                .get(&branch.name)
-                .cloned()
-                .unwrap_or_else(|| sanitize_branch_name(&branch.name));
-            let output_dir = self.output_dir.join(&branch_output_dir);
-            writeln!(
-                w,
-                "Processed branch: {} -> {}",
-                branch.name,
-                output_dir.display()
-            )?;
+        let output_dirs = self.resolve_output_dirs(
+            &selected
+                .iter()
+                .map(|x| (x.name.as_str(), x.short_hash.as_str()))
+                .collect::<Vec<_>>(),
+        );
+        let candidates = reasons
+            .into_iter()
+            .map(|(branch, reason)| BranchCandidate {
+                output_dir: output_dirs
+                    .get(&branch.name)
+                    .map(|x| self.output_dir.join(x)),
+                name: branch.name,
+                head: branch.head_hash,
+                short_head: branch.short_hash,
+                reason,
+            })
+            .collect();
+        let tasks = selected
+            .into_iter()
+            .map(|branch| {
+                let branch_output_dir = output_dirs
+                    .get(&branch.name)
+                    .cloned()
+                    .unwrap_or_else(|| sanitize_branch_name(&branch.name));
+                let copy = FilesCopy::new(
+                    self.git_path.clone(),
+                    from_hash.clone(),
+                    branch.head_hash.clone(),
+                    root_dir.clone(),
+                    self.output_dir.join(branch_output_dir),
+                    current_commit.clone(),
+                )
+                .with_options(self.options.clone());
+                (branch, copy)
+            })
+            .collect();
+
+        Ok(Selection {
+            git_version: git.version().to_string(),
+            from_hash,
+            skipped,
+            tasks,
+            candidates,
+        })
+    }
 
-            let copy = FilesCopy::new(
-                self.git_path.clone(),
-                from_hash.clone(),
-                branch.head_hash,
-                root_dir.clone(),
-                output_dir,
-                current_commit.clone(),
-            );
-            copy.copy(w)?;
+    fn list_branches(
+        &self,
+        branches: &GitLocalBranches,
+        skipped: &mut Vec<SkippedBranch>,
+    ) -> Result<Vec<GitLocalBranch>> {
+        // Remote names may contain `/`, so the remote of a ref is found by its name
+        let mut remotes = branches.remotes()?;
+        remotes.extend(self.ref_sources.iter().filter_map(|x| match x {
+            RefSource::Remote(remote) => Some(remote.clone()),
+            _ => None,
+        }));
+        // (name without the remote, head) -> name of the first listed ref
+        let mut listed = HashMap::new();
+        let mut ret = Vec::new();
+        for source in self.ref_sources.iter() {
+            for branch in branches.list_refs(source)? {
+                // Remote-tracking refs are matched by `Remote` and by `refs/remotes/...` patterns
+                let name = match branch.ref_name.strip_prefix("refs/remotes/") {
+                    Some(remote_branch) => strip_remote(remote_branch, &remotes).to_string(),
+                    None => branch.name.clone(),
+                };
+                if let Some(first) = listed.get(&(name.clone(), branch.head_hash.clone())) {
+                    // The same ref can be matched by more than one source
+                    if *first != branch.name {
+                        skipped.push(SkippedBranch {
+                            reason: format!("same commit as {first}"),
+                            name: branch.name,
+                        });
+                    }
+                    continue;
+                }
+                listed.insert((name, branch.head_hash.clone()), branch.name.clone());
+                ret.push(branch);
+            }
         }
 
-        Ok(())
+        Ok(ret)
     }
 
     fn selection_reason(
         &self,
         branch: &GitLocalBranch,
+        (includes, excludes): (&[BranchPattern], &[BranchPattern]),
         from_hash: &str,
         from_timestamp: i64,
         max_delta: u64,
         merge_base: &GitMergeBase,
     ) -> Result<Option<String>> {
-        if self.excludes.contains(&branch.name) {
-            return Ok(Some("excluded".to_string()));
+        if !includes.is_empty() && !includes.iter().any(|x| x.matches(&branch.name)) {
+            let patterns = includes.iter().map(|x| x.text.as_str()).collect::<Vec<_>>();
+            return Ok(Some(format!(
+                "not matched by --include {}",
+                patterns.join(", ")
+            )));
+        }
+
+        if let Some(pattern) = excludes.iter().find(|x| x.matches(&branch.name)) {
+            return Ok(Some(format!("excluded by {}", pattern.text)));
         }
 
         if branch.head_hash == from_hash {
@@ -129,50 +444,97 @@ impl AutoCopy {
         Ok(None)
     }
 
-    fn resolve_output_dirs(
-        &self,
-        branches: &[GitLocalBranch],
-        revision: &GitRevision,
-    ) -> Result<HashMap<String, String>> {
-        self.resolve_output_dirs_with(branches, |head_hash| Ok(revision.short_hash(head_hash)?))
+    /// Sets the output directory of each of `candidates`, as listed by [`AutoCopy::selection`], to
+    /// the one it gets when `chosen` are the branches given to [`AutoCopy::with_branches`]. Git is
+    /// not run again.
+    pub fn resolve_candidates(&self, candidates: &mut [BranchCandidate], chosen: &[String]) {
+        let output_dirs = self.resolve_output_dirs(
+            &candidates
+                .iter()
+                .filter(|x| chosen.contains(&x.name))
+                .map(|x| (x.name.as_str(), x.short_head.as_str()))
+                .collect::<Vec<_>>(),
+        );
+        for candidate in candidates.iter_mut() {
+            candidate.output_dir = output_dirs
+                .get(&candidate.name)
+                .map(|x| self.output_dir.join(x));
+        }
     }
 
-    fn resolve_output_dirs_with<F>(
-        &self,
-        branches: &[GitLocalBranch],
-        mut short_hash: F,
-    ) -> Result<HashMap<String, String>>
-    where
-        F: FnMut(&str) -> Result<String>,
-    {
+    /// Maps the `(name, short hash)` of each branch to its output directory name
+    fn resolve_output_dirs(&self, branches: &[(&str, &str)]) -> HashMap<String, String> {
         let mut counts = HashMap::new();
         let mut sanitized = HashMap::new();
-        for branch in branches {
-            let name = sanitize_branch_name(&branch.name);
-            *counts.entry(name.clone()).or_insert(0usize) += 1;
-            sanitized.insert(branch.name.clone(), name);
+        for (name, _) in branches {
+            let sanitized_name = sanitize_branch_name(name);
+            *counts.entry(sanitized_name.clone()).or_insert(0usize) += 1;
+            sanitized.insert(*name, sanitized_name);
         }
 
         let mut used = HashSet::new();
         let mut resolved = HashMap::new();
-        for branch in branches {
-            let base = sanitized
-                .get(&branch.name)
-                .cloned()
-                .unwrap_or_else(|| sanitize_branch_name(&branch.name));
+        for (name, short_hash) in branches {
+            let base = sanitized[name].clone();
             let is_conflicted = counts.get(&base).copied().unwrap_or_default() > 1;
             let mut output_dir = base.clone();
             if self.output_with_short_hash || (is_conflicted && used.contains(&base)) {
-                let suffix = short_hash(&branch.head_hash)?;
-                output_dir = format!("{base}_{suffix}");
+                output_dir = format!("{base}_{short_hash}");
             }
 
             let output_dir = make_unique_output_dir(output_dir, &mut used);
 
-            resolved.insert(branch.name.clone(), output_dir);
+            resolved.insert(name.to_string(), output_dir);
         }
 
-        Ok(resolved)
+        resolved
+    }
+}
+
+/// A branch name glob, or a regular expression prefixed with `re:`
+struct BranchPattern {
+    text: String,
+    matcher: BranchMatcher,
+}
+
+enum BranchMatcher {
+    Glob(Pattern),
+    Regex(Regex),
+}
+
+impl BranchPattern {
+    fn parse(text: &str) -> Result<Self> {
+        let matcher = match text.strip_prefix("re:") {
+            Some(re) => BranchMatcher::Regex(
+                Regex::new(&format!("^(?:{re})$"))
+                    .with_context(|| format!("Invalid branch pattern \"{text}\""))?,
+            ),
+            None => BranchMatcher::Glob(
+                Pattern::new(text).with_context(|| format!("Invalid branch pattern \"{text}\""))?,
+            ),
+        };
+        Ok(Self {
+            text: text.to_string(),
+            matcher,
+        })
+    }
+
+    fn parse_all(texts: &[String]) -> Result<Vec<Self>> {
+        texts.iter().map(|x| Self::parse(x)).collect()
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        match &self.matcher {
+            // `*` stops at `/` like in the path filters; `**` crosses it
+            BranchMatcher::Glob(pattern) => pattern.matches_with(
+                name,
+                MatchOptions {
+                    require_literal_separator: true,
+                    ..MatchOptions::new()
+                },
+            ),
+            BranchMatcher::Regex(regex) => regex.is_match(name),
+        }
     }
 }
 
@@ -231,10 +593,21 @@ fn make_unique_output_dir(candidate: String, used: &mut HashSet<String>) -> Stri
     }
 }
 
+/// Strips the longest of `remotes` from `<remote>/<branch>`, or returns it as is when no remote
+/// matches
+fn strip_remote<'a>(remote_branch: &'a str, remotes: &[String]) -> &'a str {
+    remotes
+        .iter()
+        .filter_map(|x| remote_branch.strip_prefix(x.as_str())?.strip_prefix('/'))
+        .min_by_key(|x| x.len())
+        .unwrap_or(remote_branch)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::testutil::git_test_lock;
+    use crate::event::TextObserver;
+    use crate::report::REPORT_FILE;
     use outdir_tempdir::TempDir;
     use std::fs;
     use std::path::Path;
@@ -422,11 +795,12 @@ mod tests {
 
     #[test]
     fn auto_selects_only_local_branches_in_range() {
-        let _lock = git_test_lock();
         let repo = AutoTestRepo::new();
         let mut out = Vec::new();
 
-        repo.auto_copy(30, &[], false).copy(&mut out).unwrap();
+        repo.auto_copy(30, &[], false)
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
 
         assert!(repo
             .output_dir
@@ -447,25 +821,274 @@ mod tests {
         assert!(output.contains("Skipped branch: unrelated (base commit is not an ancestor)"));
     }
 
+    #[test]
+    fn auto_plan_selects_branches_without_writing() {
+        let repo = AutoTestRepo::new();
+
+        let plan = repo.auto_copy(30, &["main"], true).plan().unwrap();
+
+        assert!(!repo.output_dir.exists());
+        assert_eq!(repo.from_commit, plan.base);
+        let names = plan
+            .branches
+            .iter()
+            .map(|x| x.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec!["feature/excluded", "feature/in-range", "feature/short-hash"],
+            names
+        );
+        let in_range = &plan.branches[1];
+        assert_eq!(repo.in_range_branch_hash, in_range.head);
+        assert_eq!(repo.in_range_branch_hash, in_range.plan.to);
+        let short_hash = run_git(
+            &repo.repo_dir,
+            &["rev-parse", "--short", &repo.in_range_branch_hash],
+        );
+        let output_dir = repo
+            .output_dir
+            .join(format!("feature_in-range_{}", short_hash.trim()));
+        assert_eq!(output_dir, in_range.plan.output_dir);
+        assert!(in_range
+            .plan
+            .writes
+            .contains(&output_dir.join("to").join("shared.txt")));
+        assert!(plan
+            .skipped
+            .iter()
+            .any(|x| x.name == "main" && x.reason == "excluded by main"));
+    }
+
+    #[test]
+    fn auto_selection_lists_reasons_and_honors_chosen_branches() {
+        let repo = AutoTestRepo::new();
+        let reason = |candidates: &[BranchCandidate], name: &str| {
+            let candidate = candidates.iter().find(|x| x.name == name).unwrap();
+            (candidate.reason.clone(), candidate.output_dir.clone())
+        };
+
+        let candidates = repo.auto_copy(30, &["main"], false).selection().unwrap();
+        assert!(!repo.output_dir.exists());
+        assert_eq!(
+            (None, Some(repo.output_dir.join("feature_in-range"))),
+            reason(&candidates, "feature/in-range")
+        );
+        assert_eq!(
+            (Some("outside --days range".to_string()), None),
+            reason(&candidates, "feature/out-of-range")
+        );
+        assert_eq!(
+            (Some("excluded by main".to_string()), None),
+            reason(&candidates, "main")
+        );
+
+        let auto_copy = repo
+            .auto_copy(30, &["main"], false)
+            .with_branches(["feature/out-of-range".to_string()]);
+        let candidates = auto_copy.selection().unwrap();
+        assert_eq!(
+            (
+                Some("outside --days range".to_string()),
+                Some(repo.output_dir.join("feature_out-of-range"))
+            ),
+            reason(&candidates, "feature/out-of-range")
+        );
+        assert_eq!(None, reason(&candidates, "feature/in-range").1);
+
+        // Resolving the listed candidates gives the same directories without running git
+        let mut resolved = repo.auto_copy(30, &["main"], false).selection().unwrap();
+        repo.auto_copy(30, &["main"], false)
+            .resolve_candidates(&mut resolved, &["feature/out-of-range".to_string()]);
+        assert_eq!(candidates, resolved);
+
+        let report = auto_copy.run(&mut TextObserver::new(Vec::new())).unwrap();
+        let names = report
+            .branches
+            .iter()
+            .map(|x| x.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["feature/out-of-range"], names);
+        assert!(report
+            .skipped
+            .iter()
+            .any(|x| x.name == "feature/in-range" && x.reason == "not chosen"));
+        assert!(repo
+            .output_dir
+            .join("feature_out-of-range")
+            .join("to")
+            .exists());
+    }
+
+    #[test]
+    fn auto_lists_remote_branches_and_skips_duplicates() {
+        let repo = AutoTestRepo::new();
+        run_git(
+            &repo.repo_dir,
+            &[
+                "update-ref",
+                "refs/remotes/origin/feature/in-range",
+                &repo.in_range_branch_hash,
+            ],
+        );
+        run_git(
+            &repo.repo_dir,
+            &[
+                "symbolic-ref",
+                "refs/remotes/origin/HEAD",
+                "refs/remotes/origin/remote-only",
+            ],
+        );
+        let mut out = Vec::new();
+
+        repo.auto_copy(30, &[], false)
+            .with_ref_sources([RefSource::Local, RefSource::Remote("origin".into())])
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
+
+        assert!(repo.output_dir.join("feature_in-range").join("to").exists());
+        assert!(repo
+            .output_dir
+            .join("origin_remote-only")
+            .join("to")
+            .join("remote.txt")
+            .exists());
+        assert!(!repo.output_dir.join("origin_feature_in-range").exists());
+
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("Selected branch count: 4"));
+        assert!(output
+            .contains("Skipped branch: origin/feature/in-range (same commit as feature/in-range)"));
+
+        // The same remote branch matched by a for-each-ref pattern
+        // A remote whose name contains `/`, next to one whose name is its first component
+        run_git(&repo.repo_dir, &["remote", "add", "origin", "../origin"]);
+        run_git(&repo.repo_dir, &["remote", "add", "team", "../team"]);
+        run_git(&repo.repo_dir, &["remote", "add", "team/a", "../team-a"]);
+        run_git(
+            &repo.repo_dir,
+            &[
+                "update-ref",
+                "refs/remotes/team/a/feature/in-range",
+                &repo.in_range_branch_hash,
+            ],
+        );
+        let plan = repo
+            .auto_copy(30, &[], false)
+            .with_ref_sources([
+                RefSource::Local,
+                RefSource::Pattern("refs/remotes/origin".into()),
+                RefSource::Pattern("refs/remotes/team".into()),
+            ])
+            .plan()
+            .unwrap();
+        for name in ["origin/feature/in-range", "team/a/feature/in-range"] {
+            assert!(plan
+                .skipped
+                .iter()
+                .any(|x| x.name == name && x.reason == "same commit as feature/in-range"));
+            assert!(plan.branches.iter().all(|x| x.name != name));
+        }
+    }
+
+    #[test]
+    fn auto_lists_tags_and_for_each_ref_patterns() {
+        let repo = AutoTestRepo::new();
+        run_git(
+            &repo.repo_dir,
+            &[
+                "tag",
+                "-a",
+                "v1.0",
+                "-m",
+                "v1.0",
+                &repo.in_range_branch_hash,
+            ],
+        );
+        let mut out = Vec::new();
+
+        repo.auto_copy(30, &[], false)
+            .with_ref_sources([
+                RefSource::Tags,
+                RefSource::Pattern("refs/remotes/origin/remote-only".into()),
+            ])
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
+
+        assert!(repo
+            .output_dir
+            .join("v1.0")
+            .join("to")
+            .join("shared.txt")
+            .exists());
+        assert!(repo
+            .output_dir
+            .join("origin_remote-only")
+            .join("to")
+            .exists());
+        assert!(!repo.output_dir.join("feature_in-range").exists());
+
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("Selected branch count: 2"));
+    }
+
     #[test]
     fn auto_honors_exclude_option() {
-        let _lock = git_test_lock();
         let repo = AutoTestRepo::new();
         let mut out = Vec::new();
 
         repo.auto_copy(30, &["main", "feature/excluded"], false)
-            .copy(&mut out)
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
+
+        assert!(!repo.output_dir.join("feature_excluded").exists());
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("Skipped branch: main (excluded by main)"));
+        assert!(output.contains("Skipped branch: feature/excluded (excluded by feature/excluded)"));
+    }
+
+    #[test]
+    fn auto_honors_include_and_exclude_patterns() {
+        let repo = AutoTestRepo::new();
+        let mut out = Vec::new();
+
+        repo.auto_copy(30, &["re:.*/ex.+"], false)
+            .with_includes(["feature/*".to_string()])
+            .copy(&mut TextObserver::new(&mut out))
             .unwrap();
 
+        assert!(repo.output_dir.join("feature_in-range").join("to").exists());
+        assert!(repo
+            .output_dir
+            .join("feature_short-hash")
+            .join("to")
+            .exists());
         assert!(!repo.output_dir.join("feature_excluded").exists());
         let output = String::from_utf8(out).unwrap();
-        assert!(output.contains("Skipped branch: main (excluded)"));
-        assert!(output.contains("Skipped branch: feature/excluded (excluded)"));
+        assert!(output.contains("Selected branch count: 2"));
+        assert!(output.contains("Skipped branch: main (not matched by --include feature/*)"));
+        assert!(output.contains("Skipped branch: feature/excluded (excluded by re:.*/ex.+)"));
+    }
+
+    #[test]
+    fn branch_patterns_match_whole_names() {
+        let glob = BranchPattern::parse("release/2026-*").unwrap();
+        assert!(glob.matches("release/2026-01"));
+        assert!(!glob.matches("release/2026-01/hotfix"));
+        assert!(!glob.matches("old/release/2026-01"));
+        assert!(BranchPattern::parse("feature/**")
+            .unwrap()
+            .matches("feature/a/b"));
+
+        let regex = BranchPattern::parse("re:release/20[0-9]{2}-.*").unwrap();
+        assert!(regex.matches("release/2026-01"));
+        assert!(!regex.matches("old/release/2026-01"));
+
+        assert!(BranchPattern::parse("re:(").is_err());
+        assert!(BranchPattern::parse("[").is_err());
     }
 
     #[test]
     fn auto_appends_short_hash_only_when_requested() {
-        let _lock = git_test_lock();
         let repo = AutoTestRepo::new();
         let short_hash = run_git(
             &repo.repo_dir,
@@ -475,7 +1098,9 @@ mod tests {
         .to_string();
         let mut out = Vec::new();
 
-        repo.auto_copy(30, &[], true).copy(&mut out).unwrap();
+        repo.auto_copy(30, &[], true)
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
 
         assert!(repo
             .output_dir
@@ -487,27 +1112,13 @@ mod tests {
     #[test]
     fn auto_avoids_overwriting_when_sanitized_names_collide() {
         let auto = AutoCopy::new("git", "HEAD", 30, ".", "out", Vec::<String>::new(), false);
-        let branches = vec![
-            GitLocalBranch {
-                name: "feature/foo".to_string(),
-                head_hash: "1111111111111111111111111111111111111111".to_string(),
-                committer_timestamp: 1,
-            },
-            GitLocalBranch {
-                name: "feature:foo".to_string(),
-                head_hash: "1111111111111111111111111111111111111111".to_string(),
-                committer_timestamp: 2,
-            },
-            GitLocalBranch {
-                name: "feature*foo".to_string(),
-                head_hash: "1111111111111111111111111111111111111111".to_string(),
-                committer_timestamp: 3,
-            },
+        let branches = [
+            ("feature/foo", "1111111"),
+            ("feature:foo", "1111111"),
+            ("feature*foo", "1111111"),
         ];
 
-        let resolved = auto
-            .resolve_output_dirs_with(&branches, |head_hash| Ok(head_hash[..7].to_string()))
-            .unwrap();
+        let resolved = auto.resolve_output_dirs(&branches);
         assert_eq!(resolved.get("feature/foo").unwrap(), "feature_foo");
         assert_eq!(resolved.get("feature:foo").unwrap(), "feature_foo_1111111");
         assert_eq!(
@@ -519,27 +1130,13 @@ mod tests {
     #[test]
     fn auto_avoids_overwriting_when_short_hash_output_names_collide() {
         let auto = AutoCopy::new("git", "HEAD", 30, ".", "out", Vec::<String>::new(), true);
-        let branches = vec![
-            GitLocalBranch {
-                name: "feature/foo".to_string(),
-                head_hash: "1111111111111111111111111111111111111111".to_string(),
-                committer_timestamp: 1,
-            },
-            GitLocalBranch {
-                name: "feature:foo".to_string(),
-                head_hash: "1111111111111111111111111111111111111111".to_string(),
-                committer_timestamp: 2,
-            },
-            GitLocalBranch {
-                name: "feature*foo".to_string(),
-                head_hash: "1111111111111111111111111111111111111111".to_string(),
-                committer_timestamp: 3,
-            },
+        let branches = [
+            ("feature/foo", "1111111"),
+            ("feature:foo", "1111111"),
+            ("feature*foo", "1111111"),
         ];
 
-        let resolved = auto
-            .resolve_output_dirs_with(&branches, |head_hash| Ok(head_hash[..7].to_string()))
-            .unwrap();
+        let resolved = auto.resolve_output_dirs(&branches);
         assert_eq!(resolved.get("feature/foo").unwrap(), "feature_foo_1111111");
         assert_eq!(
             resolved.get("feature:foo").unwrap(),
@@ -551,9 +1148,108 @@ mod tests {
         );
     }
 
+    #[test]
+    fn auto_processes_branches_in_parallel_with_grouped_output() {
+        let repo = AutoTestRepo::new();
+        let mut out = Vec::new();
+
+        repo.auto_copy(30, &[], false)
+            .with_jobs(3)
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
+
+        for dir in ["feature_in-range", "feature_excluded", "feature_short-hash"] {
+            assert!(repo.output_dir.join(dir).join("to").exists());
+        }
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("Succeeded branch count: 3"));
+        assert!(output.contains("Failed branch count: 0"));
+
+        // Every block starts with its branch and is not interleaved with another branch
+        let blocks = output
+            .split("Processed branch: ")
+            .skip(1)
+            .collect::<Vec<_>>();
+        assert_eq!(3, blocks.len());
+        for block in blocks {
+            let dir = block.split(" -> ").nth(1).unwrap().lines().next().unwrap();
+            for line in block.lines().filter(|x| x.starts_with("Copied: ")) {
+                assert!(line.contains(dir), "{line} is not in {dir}");
+            }
+        }
+    }
+
+    #[test]
+    fn auto_run_reports_branches_skips_and_failures() {
+        let repo = AutoTestRepo::new();
+        fs::create_dir_all(&repo.output_dir).unwrap();
+        fs::write(repo.output_dir.join("feature_excluded"), b"in the way\n").unwrap();
+        let mut out = Vec::new();
+
+        let report = repo
+            .auto_copy(30, &[], false)
+            .run(&mut TextObserver::new(&mut out))
+            .unwrap();
+
+        assert_eq!(repo.from_commit, report.base);
+        let names = report
+            .branches
+            .iter()
+            .map(|x| x.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec!["feature/excluded", "feature/in-range", "feature/short-hash"],
+            names
+        );
+        let failed = report.failed();
+        assert_eq!(1, failed.len());
+        assert_eq!("feature/excluded", failed[0].name);
+        assert!(failed[0].report.is_none());
+        let in_range = &report.branches[1];
+        assert!(in_range.error.is_none());
+        assert_eq!(
+            repo.in_range_branch_hash,
+            in_range.report.as_ref().unwrap().to
+        );
+        assert!(in_range.output_dir.join(REPORT_FILE).is_file());
+        assert!(report
+            .skipped
+            .iter()
+            .any(|x| x.name == "unrelated" && x.reason == "base commit is not an ancestor"));
+
+        let written: AutoReport =
+            serde_json::from_slice(&fs::read(repo.output_dir.join(REPORT_FILE)).unwrap()).unwrap();
+        assert_eq!(report, written);
+    }
+
+    #[test]
+    fn auto_continues_after_a_failing_branch() {
+        let repo = AutoTestRepo::new();
+        fs::create_dir_all(&repo.output_dir).unwrap();
+        // A file where the output directory should be makes this branch fail
+        fs::write(repo.output_dir.join("feature_excluded"), b"in the way\n").unwrap();
+        let mut out = Vec::new();
+
+        let ret = repo
+            .auto_copy(30, &[], false)
+            .with_jobs(2)
+            .copy(&mut TextObserver::new(&mut out));
+
+        assert!(ret.is_err());
+        assert!(repo.output_dir.join("feature_in-range").join("to").exists());
+        assert!(repo
+            .output_dir
+            .join("feature_short-hash")
+            .join("to")
+            .exists());
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("Failed branch: feature/excluded ("));
+        assert!(output.contains("Succeeded branch count: 2"));
+        assert!(output.contains("Failed branch count: 1\n\tfeature/excluded\n"));
+    }
+
     #[test]
     fn auto_preserves_working_tree_staged_and_untracked_files() {
-        let _lock = git_test_lock();
         let repo = AutoTestRepo::new();
         write_bytes(repo.repo_dir.join("shared.txt"), b"dirty\n");
         run_git(&repo.repo_dir, &["add", "shared.txt"]);
@@ -563,7 +1259,9 @@ mod tests {
             .to_string();
         let mut out = Vec::new();
 
-        repo.auto_copy(30, &[], false).copy(&mut out).unwrap();
+        repo.auto_copy(30, &[], false)
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
 
         assert_eq!(
             head_before,
@@ -0,0 +1,395 @@
+use anyhow::{anyhow, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use gde::event::{Event, Observer};
+use gde::plan::BranchCandidate;
+use gde::report::AutoReport;
+use gde::AutoCopy;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::Frame;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread::{self, JoinHandle};
+
+/// What the user chose on the auto screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutoAction {
+    /// Go back to the commit list
+    Back,
+
+    Quit,
+}
+
+/// The arguments of the [`AutoCopy`] the screen runs
+pub struct AutoSettings {
+    pub git_path: String,
+    pub from: String,
+    pub days: u64,
+    pub target_dir: PathBuf,
+    pub output_dir: PathBuf,
+}
+
+impl AutoSettings {
+    /// An [`AutoCopy`] of the `chosen` branches, or of the branches the selection picks
+    fn auto_copy(&self, chosen: Option<Vec<String>>) -> AutoCopy {
+        let auto_copy = AutoCopy::new(
+            &self.git_path,
+            &self.from,
+            self.days,
+            &self.target_dir,
+            &self.output_dir,
+            Vec::new(),
+            false,
+        );
+        match chosen {
+            Some(names) => auto_copy.with_branches(names),
+            None => auto_copy,
+        }
+    }
+}
+
+/// How far the copy of a branch has got
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Progress {
+    Waiting,
+    /// `most` counts every path of the changes, but only regular files are copied
+    Running {
+        copied: usize,
+        most: usize,
+    },
+    Done,
+    Failed(String),
+}
+
+/// Sends the events of the run to the screen
+struct ChannelObserver(Sender<Event>);
+
+impl Observer for ChannelObserver {
+    fn notify(&mut self, event: Event) -> Result<()> {
+        self.0
+            .send(event)
+            .map_err(|_| anyhow!("The auto screen has gone away"))
+    }
+}
+
+/// A running copy
+struct Run {
+    events: Receiver<Event>,
+    handle: JoinHandle<gde::Result<AutoReport>>,
+}
+
+/// The candidate branches of [`AutoCopy`] with the reason the selection skips them, which can be
+/// toggled before copying them
+pub struct AutoScreen {
+    settings: AutoSettings,
+    candidates: Vec<BranchCandidate>,
+    chosen: HashSet<String>,
+    state: ListState,
+
+    /// Set once the copy has started
+    progress: HashMap<String, Progress>,
+    run: Option<Run>,
+    message: Option<String>,
+}
+
+impl AutoScreen {
+    pub fn new(settings: AutoSettings) -> Result<Self> {
+        let candidates = settings.auto_copy(None).selection()?;
+        let chosen = candidates
+            .iter()
+            .filter(|x| x.reason.is_none())
+            .map(|x| x.name.clone())
+            .collect();
+        let mut state = ListState::default();
+        if !candidates.is_empty() {
+            state.select(Some(0));
+        }
+        Ok(Self {
+            settings,
+            candidates,
+            chosen,
+            state,
+            progress: HashMap::new(),
+            run: None,
+            message: None,
+        })
+    }
+
+    /// The chosen branches in the order they are listed
+    fn chosen(&self) -> Vec<String> {
+        self.candidates
+            .iter()
+            .filter(|x| self.chosen.contains(&x.name))
+            .map(|x| x.name.clone())
+            .collect()
+    }
+
+    fn is_started(&self) -> bool {
+        !self.progress.is_empty() || self.run.is_some()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.run.is_some()
+    }
+
+    fn toggle(&mut self) {
+        let Some(i) = self.state.selected() else {
+            return;
+        };
+        let name = &self.candidates[i].name;
+        if !self.chosen.remove(name) {
+            self.chosen.insert(name.clone());
+        }
+        // The output directories depend on which branches are chosen
+        let chosen = self.chosen();
+        self.settings
+            .auto_copy(None)
+            .resolve_candidates(&mut self.candidates, &chosen);
+    }
+
+    fn start(&mut self) {
+        let chosen = self.chosen();
+        if chosen.is_empty() {
+            self.message = Some("No branch is chosen".to_string());
+            return;
+        }
+        self.progress = chosen
+            .iter()
+            .map(|x| (x.clone(), Progress::Waiting))
+            .collect();
+        let auto_copy = self.settings.auto_copy(Some(chosen));
+        let (tx, events) = mpsc::channel();
+        let handle = thread::spawn(move || auto_copy.run(&mut ChannelObserver(tx)));
+        self.run = Some(Run { events, handle });
+        self.message = Some("Copying...".to_string());
+    }
+
+    /// Applies the events sent since the last call and notices the end of the run
+    pub fn poll(&mut self) {
+        let Some(run) = self.run.as_ref() else {
+            return;
+        };
+        loop {
+            match run.events.try_recv() {
+                Ok(event) => update(&mut self.progress, event),
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => break,
+            }
+        }
+
+        let Some(run) = self.run.take() else {
+            return;
+        };
+        self.message = Some(match run.handle.join() {
+            Ok(Ok(report)) => format!(
+                "{} of {} branches copied into {}",
+                report.branches.len() - report.failed().len(),
+                report.branches.len(),
+                report.output_dir.display()
+            ),
+            Ok(Err(e)) => format!("Failed to copy the branches ({e})"),
+            Err(_) => "The copy stopped unexpectedly".to_string(),
+        });
+    }
+
+    fn next(&mut self) {
+        if let Some(i) = self.state.selected() {
+            self.state.select(Some((i + 1) % self.candidates.len()));
+        }
+    }
+
+    fn prev(&mut self) {
+        if let Some(i) = self.state.selected() {
+            self.state
+                .select(Some(i.checked_sub(1).unwrap_or(self.candidates.len() - 1)));
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<AutoAction> {
+        if self.is_running() {
+            // The worktrees are removed only when the run ends
+            return None;
+        }
+        match (key.code, key.modifiers) {
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => return Some(AutoAction::Quit),
+            (KeyCode::Esc | KeyCode::Backspace, _) => return Some(AutoAction::Back),
+            (KeyCode::Down, KeyModifiers::NONE) => self.next(),
+            (KeyCode::Up, KeyModifiers::NONE) => self.prev(),
+            (KeyCode::Char(' '), KeyModifiers::NONE) if !self.is_started() => self.toggle(),
+            (KeyCode::Enter, KeyModifiers::NONE) if !self.is_started() => self.start(),
+            _ => (),
+        }
+        None
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let [list, message, help] = Layout::vertical([
+            Constraint::Min(0),
+            Constraint::Length(3),
+            Constraint::Length(3),
+        ])
+        .areas(area);
+
+        let width = self
+            .candidates
+            .iter()
+            .map(|x| x.name.chars().count())
+            .max()
+            .unwrap_or_default();
+        let items = self
+            .candidates
+            .iter()
+            .map(|x| ListItem::new(self.to_line(x, width)))
+            .collect::<Vec<_>>();
+        let title = format!(
+            "Branches from {} within {} days (output: {})",
+            self.settings.from,
+            self.settings.days,
+            self.settings.output_dir.display()
+        );
+        let branches = List::new(items)
+            .block(Block::default().title(title).borders(Borders::ALL))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("> ");
+        frame.render_stateful_widget(branches, list, &mut self.state);
+
+        let text = self.message.clone().unwrap_or_default();
+        let message_text =
+            Paragraph::new(text).block(Block::new().borders(Borders::ALL).title("Message"));
+        frame.render_widget(message_text, message);
+
+        let keys = if self.is_running() {
+            "Copying, please wait"
+        } else if self.is_started() {
+            "Up/Down: branch  Esc: back"
+        } else {
+            "Up/Down: branch  Space: toggle  Enter: copy the checked branches  Esc: back"
+        };
+        let help_text =
+            Paragraph::new(keys).block(Block::new().borders(Borders::ALL).title("Keys"));
+        frame.render_widget(help_text, help);
+    }
+
+    fn to_line(&self, candidate: &BranchCandidate, width: usize) -> Line<'static> {
+        let check = if self.chosen.contains(&candidate.name) {
+            "[x]"
+        } else {
+            "[ ]"
+        };
+        let reason = match candidate.reason.as_deref() {
+            None => Span::styled("in range", Style::default().fg(Color::Green)),
+            Some(reason) => Span::styled(reason.to_string(), Style::default().fg(Color::Yellow)),
+        };
+        let mut spans = vec![
+            Span::raw(format!("{check} {:width$}  ", candidate.name)),
+            reason,
+        ];
+        if let Some(dir) = candidate.output_dir.as_ref().and_then(|x| x.file_name()) {
+            spans.push(Span::raw(format!("  -> {}", dir.to_string_lossy())));
+        }
+        let status = match self.progress.get(&candidate.name) {
+            None => None,
+            Some(Progress::Waiting) => Some(Span::raw("waiting")),
+            Some(Progress::Running { copied, most }) => Some(Span::styled(
+                format!("{copied} files copied (at most {most})"),
+                Style::default().fg(Color::Cyan),
+            )),
+            Some(Progress::Done) => Some(Span::styled("done", Style::default().fg(Color::Green))),
+            Some(Progress::Failed(e)) => Some(Span::styled(
+                format!("failed: {e}"),
+                Style::default().fg(Color::Red),
+            )),
+        };
+        if let Some(status) = status {
+            spans.push(Span::raw("  "));
+            spans.push(status);
+        }
+        Line::from(spans)
+    }
+}
+
+/// Applies an event of the run to the progress of its branch
+fn update(progress: &mut HashMap<String, Progress>, event: Event) {
+    match event {
+        Event::BranchStarted { name, .. } => {
+            progress.insert(name, Progress::Running { copied: 0, most: 0 });
+        }
+        Event::Branch { name, event } => {
+            let Some(Progress::Running { copied, most }) = progress.get_mut(&name) else {
+                return;
+            };
+            match *event {
+                // Both sides of a change are copied, except for added and deleted files and for
+                // symlinks and submodules, which are not known here
+                Event::Started { changes, .. } => {
+                    *most = changes
+                        .iter()
+                        .map(|x| x.from_path().is_some() as usize + x.to_path().is_some() as usize)
+                        .sum();
+                }
+                Event::FileCopied { .. } => *copied += 1,
+                _ => (),
+            }
+        }
+        Event::BranchFinished { name, error } => {
+            let state = match error {
+                Some(e) => Progress::Failed(e),
+                None => Progress::Done,
+            };
+            progress.insert(name, state);
+        }
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gde::event::CopySource;
+
+    #[test]
+    fn update_tracks_each_branch() {
+        let mut progress = HashMap::from([
+            ("a".to_string(), Progress::Waiting),
+            ("b".to_string(), Progress::Waiting),
+        ]);
+        let branch = |event| Event::Branch {
+            name: "a".to_string(),
+            event: Box::new(event),
+        };
+        update(
+            &mut progress,
+            Event::BranchStarted {
+                name: "a".to_string(),
+                output_dir: PathBuf::from("out/a"),
+            },
+        );
+        update(
+            &mut progress,
+            branch(Event::FileCopied {
+                source: CopySource::File(PathBuf::from("x")),
+                dest: PathBuf::from("out/a/from/x"),
+            }),
+        );
+        assert_eq!(
+            Some(&Progress::Running { copied: 1, most: 0 }),
+            progress.get("a")
+        );
+        assert_eq!(Some(&Progress::Waiting), progress.get("b"));
+
+        update(
+            &mut progress,
+            Event::BranchFinished {
+                name: "a".to_string(),
+                error: Some("oops".to_string()),
+            },
+        );
+        assert_eq!(
+            Some(&Progress::Failed("oops".to_string())),
+            progress.get("a")
+        );
+    }
+}
@@ -0,0 +1,310 @@
+use gde::git::{Commit, OnelineLog};
+use ratatui::widgets::ListState;
+
+/// Whether `commit` has `query` in its hash, message, author or aliases, ignoring case
+pub fn matches(commit: &Commit, query: &str) -> bool {
+    let query = query.to_lowercase();
+    [
+        commit.hash(),
+        commit.message(),
+        commit.author(),
+        commit.aliases().unwrap_or_default(),
+    ]
+    .iter()
+    .any(|x| x.to_lowercase().contains(&query))
+}
+
+pub struct StatefullTermOnelineLog {
+    pub state: ListState,
+    items: Vec<OnelineLog>,
+
+    /// Indices of the shown items. `state` selects one of these.
+    visible: Vec<usize>,
+
+    /// Only commits matching this are shown
+    filter: Option<String>,
+}
+
+impl StatefullTermOnelineLog {
+    pub fn new(items: Vec<OnelineLog>) -> Self {
+        let visible = (0..items.len()).collect();
+        Self {
+            state: ListState::default(),
+            items,
+            visible,
+            filter: None,
+        }
+    }
+
+    /// Appends lines read after the first ones, applying the filter to them
+    pub fn extend(&mut self, items: Vec<OnelineLog>) {
+        self.items.extend(items);
+        self.set_filter(self.filter.clone());
+    }
+
+    /// Number of shown lines below the selected one
+    pub fn lines_below(&self) -> usize {
+        let above = self.state.selected().map_or(0, |i| i + 1);
+        self.visible.len().saturating_sub(above)
+    }
+
+    /// The shown items
+    pub fn visible(&self) -> impl Iterator<Item = &OnelineLog> {
+        self.visible.iter().map(|&i| &self.items[i])
+    }
+
+    pub fn visible_commit_count(&self) -> usize {
+        self.visible()
+            .filter(|x| matches!(x, OnelineLog::Commit(_)))
+            .count()
+    }
+
+    pub fn filter(&self) -> Option<&str> {
+        self.filter.as_deref()
+    }
+
+    /// The text of a shown item. While filtered the commits are listed without the graph, whose
+    /// lanes would lead to hidden commits.
+    pub fn line(&self, item: &OnelineLog) -> String {
+        match item {
+            OnelineLog::Commit(commit) if self.filter.is_some() => {
+                commit.without_graph().to_string()
+            }
+            _ => item.to_string(),
+        }
+    }
+
+    /// Shows only the commits matching `filter` without the graph lines, or every line when it is
+    /// `None`
+    pub fn set_filter(&mut self, filter: Option<String>) {
+        let current = self.state.selected().map(|i| self.visible[i]);
+        self.filter = filter.filter(|x| !x.is_empty());
+        self.visible = match self.filter.as_deref() {
+            None => (0..self.items.len()).collect(),
+            Some(query) => self
+                .items
+                .iter()
+                .enumerate()
+                .filter(|(_, x)| matches!(x, OnelineLog::Commit(commit) if matches(commit, query)))
+                .map(|(i, _)| i)
+                .collect(),
+        };
+
+        let selected = current
+            .and_then(|x| self.visible.iter().position(|&i| i == x))
+            .or_else(|| self.nearest_commit(0, true));
+        self.state.select(selected);
+    }
+
+    fn is_commit(&self, i: usize) -> bool {
+        matches!(self.items[self.visible[i]], OnelineLog::Commit(_))
+    }
+
+    /// Index of the first shown commit at or after `i` (`forward`), or at or before `i`
+    fn nearest_commit(&self, i: usize, forward: bool) -> Option<usize> {
+        if self.visible.is_empty() {
+            return None;
+        }
+        let i = i.min(self.visible.len() - 1);
+        if forward {
+            (i..self.visible.len()).find(|&x| self.is_commit(x))
+        } else {
+            (0..=i).rev().find(|&x| self.is_commit(x))
+        }
+    }
+
+    /// Selects the commit `n` lines below (or above when `n` is negative) the current one,
+    /// stopping at the first and the last commit
+    pub fn move_by(&mut self, n: isize) {
+        let Some(i) = self.state.selected() else {
+            return self.first();
+        };
+        let target = i.saturating_add_signed(n);
+        let selected = self
+            .nearest_commit(target, n > 0)
+            .or_else(|| self.nearest_commit(target, n <= 0));
+        self.state.select(selected.or(Some(i)));
+    }
+
+    pub fn first(&mut self) {
+        self.state.select(self.nearest_commit(0, true));
+    }
+
+    pub fn last(&mut self) {
+        self.state.select(self.nearest_commit(usize::MAX, false));
+    }
+
+    pub fn next(&mut self) {
+        let next = self
+            .state
+            .selected()
+            .and_then(|i| self.nearest_commit(i + 1, true).filter(|&x| x > i));
+        match next {
+            Some(i) => self.state.select(Some(i)),
+            None => self.first(),
+        }
+    }
+
+    pub fn prev(&mut self) {
+        let prev = self
+            .state
+            .selected()
+            .filter(|&i| i > 0)
+            .and_then(|i| self.nearest_commit(i - 1, false));
+        match prev {
+            Some(i) => self.state.select(Some(i)),
+            None => self.last(),
+        }
+    }
+
+    /// Selects the next (`forward`) or previous shown commit matching `query`, wrapping around.
+    /// Returns false if no shown commit matches.
+    pub fn search(&mut self, query: &str, forward: bool, include_current: bool) -> bool {
+        let len = self.visible.len();
+        if len == 0 {
+            return false;
+        }
+        let start = self.state.selected().unwrap_or(0);
+        let found = (0..len)
+            .map(|x| {
+                let offset = if include_current { x } else { x + 1 };
+                if forward {
+                    (start + offset) % len
+                } else {
+                    (start + len * 2 - offset) % len
+                }
+            })
+            .find(|&i| match &self.items[self.visible[i]] {
+                OnelineLog::Commit(commit) => matches(commit, query),
+                OnelineLog::TreeBranches(_) => false,
+            });
+        if found.is_some() {
+            self.state.select(found);
+        }
+        found.is_some()
+    }
+
+    pub fn current(&self) -> Option<&OnelineLog> {
+        self.state.selected().map(|i| &self.items[self.visible[i]])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(lines: &[&str]) -> StatefullTermOnelineLog {
+        let mut sl = StatefullTermOnelineLog::new(lines.iter().map(OnelineLog::from).collect());
+        sl.first();
+        sl
+    }
+
+    fn shown(sl: &StatefullTermOnelineLog) -> Vec<String> {
+        sl.visible().map(|x| sl.line(x)).collect()
+    }
+
+    fn current_hash(sl: &StatefullTermOnelineLog) -> &str {
+        match sl.current() {
+            Some(OnelineLog::Commit(c)) => c.hash(),
+            _ => "",
+        }
+    }
+
+    const GRAPH: [&str; 7] = [
+        "*   aaaaaaa - (HEAD -> main) Merge feature (2024-01-05 00:00:00 +0900) <Alice>",
+        "|\\  ",
+        "| * bbbbbbb - (feature) Add search (2024-01-04 00:00:00 +0900) <Bob>",
+        "| * ccccccc - Add filter (2024-01-03 00:00:00 +0900) <Bob>",
+        "|/  ",
+        "* ddddddd - (tag: v1.0) Fix typo (2024-01-02 00:00:00 +0900) <Alice>",
+        "* eeeeeee - Initial commit (2024-01-01 00:00:00 +0900) <Alice>",
+    ];
+
+    #[test]
+    fn search_matches_hash_message_author_and_aliases() {
+        let mut sl = log(&GRAPH);
+        assert!(sl.search("BOB", true, false));
+        assert_eq!("bbbbbbb", current_hash(&sl));
+        assert!(sl.search("bob", true, false));
+        assert_eq!("ccccccc", current_hash(&sl));
+        assert!(sl.search("v1.0", true, false));
+        assert_eq!("ddddddd", current_hash(&sl));
+        assert!(sl.search("eeee", true, false));
+        assert_eq!("eeeeeee", current_hash(&sl));
+        // Wraps around
+        assert!(sl.search("merge", true, false));
+        assert_eq!("aaaaaaa", current_hash(&sl));
+        assert!(sl.search("bob", false, false));
+        assert_eq!("ccccccc", current_hash(&sl));
+        assert!(!sl.search("nothing", true, false));
+        assert_eq!("ccccccc", current_hash(&sl));
+    }
+
+    const FLAT: [&str; 5] = [
+        "* aaaaaaa - (HEAD -> main) Merge feature (2024-01-05 00:00:00 +0900) <Alice>",
+        "* bbbbbbb - (feature) Add search (2024-01-04 00:00:00 +0900) <Bob>",
+        "* ccccccc - Add filter (2024-01-03 00:00:00 +0900) <Bob>",
+        "* ddddddd - (tag: v1.0) Fix typo (2024-01-02 00:00:00 +0900) <Alice>",
+        "* eeeeeee - Initial commit (2024-01-01 00:00:00 +0900) <Alice>",
+    ];
+
+    #[test]
+    fn filter_lists_matching_commits_without_the_graph() {
+        let mut sl = log(&GRAPH);
+        sl.set_filter(Some("bob".into()));
+        assert_eq!(vec![FLAT[1], FLAT[2]], shown(&sl));
+        assert_eq!("bbbbbbb", current_hash(&sl));
+
+        sl.set_filter(Some("alice".into()));
+        assert_eq!(vec![FLAT[0], FLAT[3], FLAT[4]], shown(&sl));
+        assert_eq!(3, sl.visible_commit_count());
+
+        sl.set_filter(Some("a".into()));
+        assert_eq!(FLAT.to_vec(), shown(&sl));
+
+        sl.set_filter(Some("nothing".into()));
+        assert!(shown(&sl).is_empty());
+        assert!(sl.current().is_none());
+
+        sl.set_filter(None);
+        assert_eq!(GRAPH.to_vec(), shown(&sl));
+    }
+
+    #[test]
+    fn extend_keeps_the_selection_and_the_filter() {
+        let mut sl = log(&GRAPH[..3]);
+        sl.set_filter(Some("bob".into()));
+        assert_eq!(vec![FLAT[1]], shown(&sl));
+        assert_eq!(0, sl.lines_below());
+
+        sl.extend(GRAPH[3..].iter().map(OnelineLog::from).collect());
+        assert_eq!(vec![FLAT[1], FLAT[2]], shown(&sl));
+        assert_eq!("bbbbbbb", current_hash(&sl));
+        assert_eq!(1, sl.lines_below());
+
+        let mut sl = log(&[]);
+        assert!(sl.current().is_none());
+        sl.extend(GRAPH.iter().map(OnelineLog::from).collect());
+        assert_eq!("aaaaaaa", current_hash(&sl));
+    }
+
+    #[test]
+    fn navigation_skips_graph_lines() {
+        let mut sl = log(&GRAPH);
+        assert_eq!("aaaaaaa", current_hash(&sl));
+        sl.next();
+        assert_eq!("bbbbbbb", current_hash(&sl));
+        sl.prev();
+        sl.prev();
+        assert_eq!("eeeeeee", current_hash(&sl));
+        sl.first();
+        sl.move_by(4);
+        assert_eq!("ddddddd", current_hash(&sl));
+        sl.move_by(100);
+        assert_eq!("eeeeeee", current_hash(&sl));
+        sl.move_by(-5);
+        assert_eq!("aaaaaaa", current_hash(&sl));
+        sl.last();
+        assert_eq!("eeeeeee", current_hash(&sl));
+    }
+}
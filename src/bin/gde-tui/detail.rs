@@ -0,0 +1,120 @@
+use gde::git::{CommitDetail, GitShow};
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
+use ratatui::Frame;
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+type Loaded = (String, Result<CommitDetail, String>);
+
+/// The details of the commits the cursor has been on, read from git on another thread the first
+/// time, so moving through the graph does not wait for git
+pub struct CommitDetails {
+    requests: Sender<String>,
+    loaded: Receiver<Loaded>,
+    cache: HashMap<String, Result<CommitDetail, String>>,
+
+    /// The last requested commit, until its details arrive
+    pending: Option<String>,
+}
+
+impl CommitDetails {
+    pub fn new(git: GitShow) -> Self {
+        let (requests, rx) = mpsc::channel::<String>();
+        let (tx, loaded) = mpsc::channel();
+        // Dropping the details closes the channel, which stops the thread
+        thread::spawn(move || {
+            while let Ok(mut hash) = rx.recv() {
+                // Commits the cursor has already left are not read
+                while let Ok(newer) = rx.try_recv() {
+                    hash = newer;
+                }
+                let detail = git.commit(&hash).map_err(|e| e.to_string());
+                if tx.send((hash, detail)).is_err() {
+                    break;
+                }
+            }
+        });
+        Self {
+            requests,
+            loaded,
+            cache: HashMap::new(),
+            pending: None,
+        }
+    }
+
+    /// Asks for the details of `hash` unless they were already read or asked for
+    pub fn load(&mut self, hash: &str) {
+        if self.cache.contains_key(hash) || self.pending.as_deref() == Some(hash) {
+            return;
+        }
+        self.pending = self
+            .requests
+            .send(hash.to_string())
+            .is_ok()
+            .then(|| hash.to_string());
+    }
+
+    /// Stores the details read since the last call
+    pub fn poll(&mut self) {
+        while let Ok((hash, detail)) = self.loaded.try_recv() {
+            if self.pending.as_deref() == Some(&hash) {
+                self.pending = None;
+            }
+            self.cache.insert(hash, detail);
+        }
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    pub fn render(&self, hash: Option<&str>, frame: &mut Frame, area: Rect) {
+        let block = Block::default().title("commit").borders(Borders::ALL);
+        let lines = match (hash, hash.and_then(|x| self.cache.get(x))) {
+            (_, Some(Ok(detail))) => to_lines(detail),
+            (_, Some(Err(e))) => vec![Line::styled(e.clone(), Style::default().fg(Color::Red))],
+            (Some(hash), None) => vec![Line::styled(
+                format!("Loading {hash}..."),
+                Style::default().fg(Color::DarkGray),
+            )],
+            (None, None) => Vec::new(),
+        };
+        let detail = Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false });
+        frame.render_widget(detail, area);
+    }
+}
+
+fn to_lines(detail: &CommitDetail) -> Vec<Line<'static>> {
+    let label = Style::default().add_modifier(Modifier::BOLD);
+    let mut lines = vec![
+        Line::styled(
+            format!("commit {}", detail.hash),
+            Style::default().fg(Color::Yellow),
+        ),
+        Line::from(format!("Parents:    {}", detail.parents.join(" "))),
+        Line::from(format!("Author:     {}", detail.author)),
+        Line::from(format!("AuthorDate: {}", detail.author_date)),
+        Line::from(format!("Commit:     {}", detail.committer)),
+        Line::from(format!("CommitDate: {}", detail.committer_date)),
+        Line::raw(""),
+    ];
+    lines.extend(
+        detail
+            .message
+            .lines()
+            .map(|x| Line::raw(format!("    {x}"))),
+    );
+    lines.push(Line::raw(""));
+    lines.push(Line::styled(
+        format!("Files ({}):", detail.files.len()),
+        label,
+    ));
+    lines.extend(detail.files.iter().map(|x| Line::raw(x.to_string())));
+    lines
+}
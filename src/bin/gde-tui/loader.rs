@@ -0,0 +1,76 @@
+use gde::git::{LogLines, OnelineLog};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+
+/// Lines of the log read per request
+pub const PAGE_LINES: usize = 500;
+
+/// Reads the log on another thread, a page at a time when asked, so the commit list shows up
+/// before the whole log is read and parsed
+pub struct LogLoader {
+    requests: Sender<usize>,
+    pages: Receiver<Result<Vec<OnelineLog>, String>>,
+    loading: bool,
+    done: bool,
+}
+
+impl LogLoader {
+    pub fn new(mut lines: LogLines) -> Self {
+        let (requests, rx) = mpsc::channel::<usize>();
+        let (tx, pages) = mpsc::channel();
+        // Dropping the loader closes the channel, which stops git
+        thread::spawn(move || {
+            for n in rx {
+                let page = lines
+                    .page(n)
+                    .map(|x| x.iter().map(OnelineLog::from).collect())
+                    .map_err(|e| e.to_string());
+                let last = page.is_err() || lines.is_done();
+                if tx.send(page).is_err() || last {
+                    break;
+                }
+            }
+        });
+        Self {
+            requests,
+            pages,
+            loading: false,
+            done: false,
+        }
+    }
+
+    /// Asks for the next page unless one is being read or the whole log has been read
+    pub fn request(&mut self) {
+        if !self.loading && !self.done {
+            self.loading = self.requests.send(PAGE_LINES).is_ok();
+        }
+    }
+
+    /// Returns the page read since the last call, if there is one
+    pub fn poll(&mut self) -> Option<Result<Vec<OnelineLog>, String>> {
+        match self.pages.try_recv() {
+            Ok(page) => {
+                self.loading = false;
+                if page.as_ref().map_or(true, |x| x.len() < PAGE_LINES) {
+                    self.done = true;
+                }
+                Some(page)
+            }
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                self.loading = false;
+                self.done = true;
+                None
+            }
+        }
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.loading
+    }
+
+    /// Whether the whole log has been read
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+}
@@ -0,0 +1,592 @@
+// TODO: Need refactoring
+mod auto;
+mod commits;
+mod detail;
+mod loader;
+mod preview;
+
+use anyhow::Result;
+use auto::{AutoAction, AutoScreen, AutoSettings};
+use clap::Parser;
+use commits::StatefullTermOnelineLog;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use detail::CommitDetails;
+use gde::event::TextObserver;
+use gde::git::{GitShow, LogLines, OnelineLog};
+use gde::FilesCopy;
+use loader::LogLoader;
+use preview::{DiffPreview, PreviewAction};
+use ratatui::prelude::CrosstermBackend;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, Borders, List, ListItem, Paragraph};
+use ratatui::Terminal;
+use std::env;
+use std::fmt::Display;
+use std::io::{self, stdout, BufWriter, Stdout};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[derive(Debug, Parser)]
+struct Cli {
+    /// Path to Git executable used when Git is not in the system PATH
+    #[arg(long, value_name = "GIT EXECUTABLE")]
+    git: Option<PathBuf>,
+
+    /// Show all branches tags and remotes
+    #[arg(short, long)]
+    all: bool,
+
+    /// Show only this many commits
+    #[arg(short = 'n', long, value_name = "NUMBER")]
+    max_count: Option<usize>,
+
+    /// Show only the commits more recent than this date, such as "2024-01-01" or "2 weeks ago"
+    #[arg(long, value_name = "DATE")]
+    since: Option<String>,
+
+    /// Destination for output files
+    #[arg(short, long, value_name = "OUTPUT DIR")]
+    output: Option<PathBuf>,
+
+    /// Branches with head timestamps within this number of days are checked on the auto screen
+    #[arg(long, default_value_t = 30, value_name = "DAYS")]
+    days: u64,
+
+    /// Path to the git-managed directory for diff
+    #[arg(value_name = "TARGET REPO DIR")]
+    target: Option<PathBuf>,
+}
+
+/// The repository the terminal works on
+struct Repo {
+    git_path: String,
+    target_dir: PathBuf,
+
+    /// Where the output directories are created
+    output_dir: PathBuf,
+    days: u64,
+}
+
+impl Repo {
+    /// The copy of the files changed between `from` and `to` into a new directory
+    fn files_copy(&self, from: &str, to: &str) -> Result<FilesCopy> {
+        let current_commit =
+            gde::git::Git::from_path(&self.git_path)?.get_hash(&self.target_dir, "HEAD")?;
+        Ok(FilesCopy::new(
+            &self.git_path,
+            from,
+            to,
+            &self.target_dir,
+            self.output_dir
+                .join(format!("gde-{}", uuid::Uuid::new_v4())),
+            current_commit,
+        ))
+    }
+}
+
+fn absolute_path(path: impl AsRef<Path>) -> Result<PathBuf> {
+    let path = path.as_ref();
+    let ret = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        env::current_dir()?.join(path)
+    };
+
+    Ok(ret)
+}
+
+/// Create a string for display on the terminal
+fn to_term_string(log: &str, mark: Option<&str>) -> String {
+    match mark {
+        Some(mark) => format!("[{mark}] {log}"),
+        None => format!("    {log}"),
+    }
+}
+
+struct GdeTerminal {
+    inner: Terminal<CrosstermBackend<Stdout>>,
+    is_restored: bool,
+}
+
+impl GdeTerminal {
+    pub fn new() -> Result<Self> {
+        let mut stdout = io::stdout();
+        enable_raw_mode()?;
+        execute!(stdout, EnterAlternateScreen)?;
+        Ok(Self {
+            inner: Terminal::new(CrosstermBackend::new(stdout))?,
+            is_restored: false,
+        })
+    }
+
+    pub fn restore_terminal(&mut self) -> Result<()> {
+        if self.is_restored {
+            Ok(())
+        } else {
+            self.is_restored = true;
+            disable_raw_mode()?;
+            execute!(self.inner.backend_mut(), LeaveAlternateScreen,)?;
+            Ok(self.inner.show_cursor()?)
+        }
+    }
+
+    /// Shows the files changed between the picked commits until the user confirms or goes back
+    fn preview(&mut self, preview: &mut DiffPreview) -> Result<PreviewAction> {
+        loop {
+            preview.load_patch();
+            self.inner
+                .draw(|frame| preview.render(frame, frame.area()))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    // for Windows
+                    continue;
+                }
+                if let Some(action) = preview.handle_key(key) {
+                    return Ok(action);
+                }
+            }
+        }
+    }
+
+    /// Shows the branches of an auto copy until the user goes back
+    fn auto(&mut self, screen: &mut AutoScreen) -> Result<AutoAction> {
+        loop {
+            screen.poll();
+            self.inner
+                .draw(|frame| screen.render(frame, frame.area()))?;
+            if screen.is_running() && !event::poll(Duration::from_millis(100))? {
+                continue;
+            }
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    // for Windows
+                    continue;
+                }
+                if let Some(action) = screen.handle_key(key) {
+                    return Ok(action);
+                }
+            }
+        }
+    }
+
+    /// Lets the user pick the commits and returns the copy they confirmed
+    pub fn run(&mut self, repo: &Repo, log: LogLines) -> Result<Option<FilesCopy>> {
+        #[derive(PartialEq, Eq)]
+        struct CommitInfo {
+            hash: String,
+            message: String,
+        }
+
+        impl CommitInfo {
+            fn new(hash: impl Into<String>, message: impl Into<String>) -> Self {
+                Self {
+                    hash: hash.into(),
+                    message: message.into(),
+                }
+            }
+        }
+
+        impl Display for CommitInfo {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{} - {}", self.hash, self.message)
+            }
+        }
+
+        fn to_notice_string(v: Option<&CommitInfo>) -> String {
+            match v {
+                Some(v) => format!("{v}"),
+                None => "".into(),
+            }
+        }
+
+        /// A line the user is typing in the message box
+        enum Prompt {
+            Search(String),
+            Filter(String),
+        }
+
+        let mut sl = StatefullTermOnelineLog::new(Vec::new());
+        let mut loader = LogLoader::new(log);
+        let mut prompt: Option<Prompt> = None;
+        let mut search: Option<String> = None;
+        // A search that found nothing in the loaded commits and goes on as more are loaded
+        let mut pending_search: Option<(String, bool)> = None;
+        // Lines the commit list scrolls by on PageUp/PageDown
+        let mut page = 1;
+        let mut details = CommitDetails::new(GitShow::new(&repo.git_path, &repo.target_dir)?);
+        let mut from_commit: Option<CommitInfo> = None;
+        let mut to_commit: Option<CommitInfo> = None;
+        let mut notice_msg: Option<String> = None;
+        'outer: loop {
+            details.poll();
+            while let Some(page) = loader.poll() {
+                match page {
+                    Ok(items) => sl.extend(items),
+                    Err(e) => notice_msg = Some(format!("Failed to read the log ({e})")),
+                }
+                if let Some((text, forward)) = pending_search.take() {
+                    if sl.search(&text, forward, true) {
+                        notice_msg = None;
+                    } else {
+                        pending_search = Some((text, forward));
+                    }
+                }
+            }
+            if loader.is_done() {
+                if let Some((text, _)) = pending_search.take() {
+                    notice_msg = Some(format!("No commit matches \"{text}\""));
+                }
+            }
+            // Keep a couple of pages below the cursor loaded
+            if pending_search.is_some() || sl.lines_below() < page as usize * 2 {
+                loader.request();
+            }
+
+            let logs = sl
+                .visible()
+                .map(|x| {
+                    let line = sl.line(x);
+                    if let OnelineLog::Commit(y) = x {
+                        if let Some(ref z) = from_commit {
+                            if y.hash() == z.hash {
+                                if from_commit == to_commit {
+                                    return ListItem::new(to_term_string(&line, Some("*")));
+                                } else {
+                                    return ListItem::new(to_term_string(&line, Some("F")));
+                                }
+                            }
+                        }
+                        if let Some(ref z) = to_commit {
+                            if y.hash() == z.hash {
+                                return ListItem::new(to_term_string(&line, Some("T")));
+                            }
+                        }
+
+                        return ListItem::new(to_term_string(&line, Some(" ")));
+                    }
+                    ListItem::new(to_term_string(&line, None))
+                })
+                .collect::<Vec<_>>();
+            let mut title = match sl.filter() {
+                Some(filter) => format!(
+                    "commits (filter: {filter}, {} shown, Esc to clear)",
+                    sl.visible_commit_count()
+                ),
+                None => "commits".to_string(),
+            };
+            if loader.is_loading() {
+                title.push_str(" loading...");
+            }
+            let logs = List::new(logs)
+                .block(Block::default().title(title).borders(Borders::ALL))
+                .style(Style::default().fg(Color::White))
+                .highlight_style(Style::default().add_modifier(Modifier::ITALIC))
+                .highlight_symbol("> ");
+
+            let selected_commits = Block::new()
+                .borders(Borders::ALL)
+                .title("Selected commits (Press \"f\" to select it as the \"From Commit\". Press \"t\" to select it as the \"To Commit\".)");
+            let disp_text = format!(
+                "From: {}\nTo  : {}",
+                to_notice_string(from_commit.as_ref()),
+                to_notice_string(to_commit.as_ref())
+            );
+            let selected_commits = Paragraph::new(disp_text).block(selected_commits);
+
+            let notice = Block::new().borders(Borders::ALL).title("Message");
+            let notice_text = match (&prompt, &pending_search) {
+                (Some(Prompt::Search(text)), _) => format!("/{text}"),
+                (Some(Prompt::Filter(text)), _) => format!("&{text}"),
+                (None, Some((text, _))) => {
+                    format!("Searching older commits for \"{text}\"... (Esc to stop)")
+                }
+                (None, None) => notice_msg
+                    .as_ref()
+                    .map(|x| x as &str)
+                    .unwrap_or_default()
+                    .to_string(),
+            };
+            let notice = Paragraph::new(notice_text).block(notice);
+            let current_hash = match sl.current() {
+                Some(OnelineLog::Commit(c)) => Some(c.hash().to_string()),
+                _ => None,
+            };
+            if let Some(hash) = current_hash.as_deref() {
+                details.load(hash);
+            }
+            self.inner.draw(|frame| {
+                let mut log_size = frame.area();
+                log_size.height -= 7;
+                let mut detail_size = log_size;
+                log_size.width = log_size.width * 3 / 5;
+                detail_size.x += log_size.width;
+                detail_size.width -= log_size.width;
+                let mut sc_size = frame.area();
+                sc_size.y += log_size.height;
+                sc_size.height = 4;
+                let mut notice_size = frame.area();
+                notice_size.y += sc_size.y + sc_size.height;
+                notice_size.height = 3;
+
+                page = log_size.height.saturating_sub(3).max(1) as isize;
+                frame.render_stateful_widget(logs, log_size, &mut sl.state);
+                details.render(current_hash.as_deref(), frame, detail_size);
+                frame.render_widget(selected_commits, sc_size);
+                frame.render_widget(notice, notice_size);
+            })?;
+
+            // Redraw when a page or the details arrive while waiting for a key
+            if (loader.is_loading() || details.is_loading())
+                && !event::poll(Duration::from_millis(100))?
+            {
+                continue 'outer;
+            }
+            while let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    // for Windows
+                    break;
+                }
+                if let Some(p) = prompt.as_mut() {
+                    let text = match p {
+                        Prompt::Search(text) | Prompt::Filter(text) => text,
+                    };
+                    match (key.code, key.modifiers) {
+                        (KeyCode::Char('c'), KeyModifiers::CONTROL) => return Ok(None),
+                        (KeyCode::Esc, _) => prompt = None,
+                        (KeyCode::Backspace, _) => {
+                            text.pop();
+                        }
+                        (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
+                            text.push(c);
+                            // Incremental search
+                            if let Prompt::Search(text) = p {
+                                sl.search(text, true, true);
+                            }
+                        }
+                        (KeyCode::Enter, _) => match prompt.take() {
+                            Some(Prompt::Search(text)) => {
+                                notice_msg = None;
+                                if !sl.search(&text, true, true) {
+                                    pending_search = Some((text.clone(), true));
+                                }
+                                search = Some(text).filter(|x| !x.is_empty());
+                            }
+                            Some(Prompt::Filter(text)) => {
+                                sl.set_filter(Some(text));
+                                notice_msg = None;
+                            }
+                            None => (),
+                        },
+                        _ => (),
+                    }
+                    continue 'outer;
+                }
+                match (key.code, key.modifiers) {
+                    (KeyCode::Esc, _) if pending_search.is_some() => {
+                        pending_search = None;
+                        notice_msg = Some("Stopped searching".to_string());
+                        continue 'outer;
+                    }
+                    (KeyCode::Esc, _) if sl.filter().is_some() => {
+                        sl.set_filter(None);
+                        notice_msg = Some("Cleared the filter".to_string());
+                        continue 'outer;
+                    }
+                    (KeyCode::Esc, _) => return Ok(None),
+                    (KeyCode::Char('c'), KeyModifiers::CONTROL) => return Ok(None),
+                    (KeyCode::Enter, KeyModifiers::NONE) => match (&from_commit, &to_commit) {
+                        (Some(from), Some(to)) => {
+                            if from.hash == to.hash {
+                                notice_msg = Some("Select different commits for \"From Commit\" and \"To Commit\"".into());
+                                continue 'outer;
+                            }
+                            // The preview lists what the copy ships
+                            let files_copy = repo.files_copy(&from.hash, &to.hash)?;
+                            match self.preview(&mut DiffPreview::new(&files_copy)?)? {
+                                PreviewAction::Confirm => return Ok(Some(files_copy)),
+                                PreviewAction::Back => {
+                                    notice_msg = None;
+                                    continue 'outer;
+                                }
+                                PreviewAction::Quit => return Ok(None),
+                            }
+                        }
+                        (Some(_), None) => {
+                            notice_msg = Some("\"To Commit\" is not selected".into());
+                            continue 'outer;
+                        }
+                        (None, Some(_)) => {
+                            notice_msg = Some("\"From Commit\" is not selected".into());
+                            continue 'outer;
+                        }
+
+                        (None, None) => {
+                            notice_msg =
+                                Some("\"From Commit\" and \"To Commit\" are not selected".into());
+                            continue 'outer;
+                        }
+                    },
+                    // Wrap around only once the whole log is loaded
+                    (KeyCode::Down, KeyModifiers::NONE) if !loader.is_done() => {
+                        sl.move_by(1);
+                        continue 'outer;
+                    }
+                    (KeyCode::Up, KeyModifiers::NONE) if !loader.is_done() => {
+                        sl.move_by(-1);
+                        continue 'outer;
+                    }
+                    (KeyCode::Down, KeyModifiers::NONE) => {
+                        sl.next();
+                        continue 'outer;
+                    }
+                    (KeyCode::Up, KeyModifiers::NONE) => {
+                        sl.prev();
+                        continue 'outer;
+                    }
+                    (KeyCode::PageDown, _) => {
+                        sl.move_by(page);
+                        continue 'outer;
+                    }
+                    (KeyCode::PageUp, _) => {
+                        sl.move_by(-page);
+                        continue 'outer;
+                    }
+                    (KeyCode::Home, _) | (KeyCode::Char('g'), KeyModifiers::NONE) => {
+                        sl.first();
+                        continue 'outer;
+                    }
+                    (KeyCode::End, _) | (KeyCode::Char('G'), _) => {
+                        sl.last();
+                        continue 'outer;
+                    }
+                    (KeyCode::Char('/'), _) => {
+                        prompt = Some(Prompt::Search(String::new()));
+                        continue 'outer;
+                    }
+                    (KeyCode::Char('&'), _) => {
+                        let filter = sl.filter().unwrap_or_default().to_string();
+                        prompt = Some(Prompt::Filter(filter));
+                        continue 'outer;
+                    }
+                    (KeyCode::Char('n'), KeyModifiers::NONE) | (KeyCode::Char('N'), _) => {
+                        let forward = key.code == KeyCode::Char('n');
+                        notice_msg = match &search {
+                            Some(text) if sl.search(text, forward, false) => None,
+                            Some(text) => {
+                                pending_search = Some((text.clone(), forward));
+                                None
+                            }
+                            None => Some("Press \"/\" to search".to_string()),
+                        };
+                        continue 'outer;
+                    }
+                    (KeyCode::Char('a'), KeyModifiers::NONE) => {
+                        let base = match (&from_commit, sl.current()) {
+                            (Some(from), _) => from.hash.clone(),
+                            (None, Some(OnelineLog::Commit(c))) => c.hash().to_string(),
+                            (None, _) => continue 'outer,
+                        };
+                        let settings = AutoSettings {
+                            git_path: repo.git_path.clone(),
+                            from: base,
+                            days: repo.days,
+                            target_dir: repo.target_dir.clone(),
+                            output_dir: repo
+                                .output_dir
+                                .join(format!("gde-{}", uuid::Uuid::new_v4())),
+                        };
+                        let mut screen = match AutoScreen::new(settings) {
+                            Ok(screen) => screen,
+                            Err(e) => {
+                                notice_msg = Some(format!("Failed to list the branches ({e})"));
+                                continue 'outer;
+                            }
+                        };
+                        match self.auto(&mut screen)? {
+                            AutoAction::Back => {
+                                notice_msg = None;
+                                continue 'outer;
+                            }
+                            AutoAction::Quit => return Ok(None),
+                        }
+                    }
+                    (KeyCode::Char('f'), KeyModifiers::NONE) => {
+                        if let Some(OnelineLog::Commit(ref c)) = sl.current() {
+                            from_commit = Some(CommitInfo::new(c.hash(), c.message()));
+                            notice_msg = Some(format!("Selected {} as \"From Commit\"", c.hash()));
+                        } else {
+                            from_commit = None;
+                            notice_msg = Some("Cleared the \"From Commit\"".to_string());
+                        }
+                        continue 'outer;
+                    }
+                    (KeyCode::Char('t'), KeyModifiers::NONE) => {
+                        if let Some(OnelineLog::Commit(ref c)) = sl.current() {
+                            to_commit = Some(CommitInfo::new(c.hash(), c.message()));
+                            notice_msg = Some(format!("Selected {} as \"To Commit\"", c.hash()));
+                        } else {
+                            to_commit = None;
+                            notice_msg = Some("Cleared the \"To Commit\"".to_string());
+                        }
+                        continue 'outer;
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+}
+
+impl Drop for GdeTerminal {
+    fn drop(&mut self) {
+        self.restore_terminal().unwrap();
+    }
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let git_path = if let Some(git) = cli.git {
+        git.display().to_string()
+    } else {
+        "git".to_string()
+    };
+
+    let git = gde::git::Git::from_path(&git_path)?;
+    println!("Git version: {}", git.version());
+
+    let target_dir = if let Some(dir) = cli.target {
+        absolute_path(dir)?
+    } else {
+        env::current_dir()?
+    };
+
+    let gitlog = gde::git::GitLog::new(&git_path, cli.all, &target_dir)?
+        .with_max_count(cli.max_count)
+        .with_since(cli.since);
+    let logs = gitlog.stream()?;
+    let output_dir = if let Some(dir) = cli.output {
+        absolute_path(dir)?
+    } else {
+        env::current_dir()?
+    };
+    let repo = Repo {
+        git_path: git_path.clone(),
+        target_dir: target_dir.clone(),
+        output_dir: output_dir.clone(),
+        days: cli.days,
+    };
+    let mut term = GdeTerminal::new()?;
+    let selected = term.run(&repo, logs)?;
+    term.restore_terminal()?;
+
+    if let Some(f) = selected {
+        let out = stdout();
+        let mut out = BufWriter::new(out.lock());
+        f.copy(&mut TextObserver::new(&mut out))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,275 @@
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use gde::git::{FileChange, GitDiff};
+use gde::FilesCopy;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::Frame;
+use std::collections::HashMap;
+
+/// What the user chose on the preview screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreviewAction {
+    /// Copy the files
+    Confirm,
+
+    /// Go back to the commit list
+    Back,
+
+    Quit,
+}
+
+/// The files changed between the picked commits and the diff of the highlighted one
+pub struct DiffPreview {
+    diff: GitDiff,
+    changes: Vec<FileChange>,
+    state: ListState,
+
+    /// Colored diff of each file, loaded when the file is first highlighted
+    patches: HashMap<usize, Vec<Line<'static>>>,
+    scroll: u16,
+
+    /// Height of the diff pane when it was last drawn
+    page: u16,
+}
+
+impl DiffPreview {
+    /// Lists the files `files_copy` ships, with its path filters and `.gdeignore` applied
+    pub fn new(files_copy: &FilesCopy) -> Result<Self> {
+        let (diff, changes) = files_copy.changes()?;
+        let mut state = ListState::default();
+        if !changes.is_empty() {
+            state.select(Some(0));
+        }
+        Ok(Self {
+            diff,
+            changes,
+            state,
+            patches: HashMap::new(),
+            scroll: 0,
+            page: 0,
+        })
+    }
+
+    fn select(&mut self, i: usize) {
+        self.state.select(Some(i));
+        self.scroll = 0;
+    }
+
+    fn next(&mut self) {
+        if let Some(i) = self.state.selected() {
+            self.select((i + 1) % self.changes.len());
+        }
+    }
+
+    fn prev(&mut self) {
+        if let Some(i) = self.state.selected() {
+            self.select(i.checked_sub(1).unwrap_or(self.changes.len() - 1));
+        }
+    }
+
+    fn scroll_by(&mut self, lines: i32) {
+        let len = self
+            .state
+            .selected()
+            .and_then(|i| self.patches.get(&i))
+            .map(|x| x.len())
+            .unwrap_or_default();
+        let max = len.saturating_sub(1).min(u16::MAX as usize) as i32;
+        self.scroll = (self.scroll as i32 + lines).clamp(0, max) as u16;
+    }
+
+    /// Reads the diff of the highlighted file unless it was already read
+    pub fn load_patch(&mut self) {
+        let Some(i) = self.state.selected() else {
+            return;
+        };
+        if self.patches.contains_key(&i) {
+            return;
+        }
+        let change = &self.changes[i];
+        let mut paths = Vec::new();
+        paths.extend(change.from_path());
+        paths.extend(change.to_path().filter(|x| Some(*x) != change.from_path()));
+        let lines = match self.diff.patch(&paths) {
+            Ok(patch) => colorize(&String::from_utf8_lossy(&patch)),
+            Err(e) => vec![Line::styled(
+                format!("Failed to read the diff ({e})"),
+                Style::default().fg(Color::Red),
+            )],
+        };
+        self.patches.insert(i, lines);
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<PreviewAction> {
+        let page = self.page.saturating_sub(2).max(1) as i32;
+        match (key.code, key.modifiers) {
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => return Some(PreviewAction::Quit),
+            (KeyCode::Esc | KeyCode::Backspace, _) => return Some(PreviewAction::Back),
+            (KeyCode::Enter, KeyModifiers::NONE) => return Some(PreviewAction::Confirm),
+            (KeyCode::Down, KeyModifiers::NONE) => self.next(),
+            (KeyCode::Up, KeyModifiers::NONE) => self.prev(),
+            (KeyCode::Char('j'), KeyModifiers::NONE) => self.scroll_by(1),
+            (KeyCode::Char('k'), KeyModifiers::NONE) => self.scroll_by(-1),
+            (KeyCode::PageDown, _) | (KeyCode::Char(' '), KeyModifiers::NONE) => {
+                self.scroll_by(page)
+            }
+            (KeyCode::PageUp, _) => self.scroll_by(-page),
+            _ => (),
+        }
+        None
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let [body, help] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).areas(area);
+        let [files, diff] =
+            Layout::horizontal([Constraint::Percentage(30), Constraint::Percentage(70)])
+                .areas(body);
+        self.page = diff.height.saturating_sub(2);
+
+        let items = self
+            .changes
+            .iter()
+            .map(|x| {
+                let path = match (x.from_path(), x.to_path()) {
+                    (Some(from), Some(to)) if from != to => format!("{from} -> {to}"),
+                    (_, Some(path)) | (Some(path), None) => path.to_string(),
+                    (None, None) => String::new(),
+                };
+                ListItem::new(format!("{} {path}", x.status())).style(status_style(x))
+            })
+            .collect::<Vec<_>>();
+        let title = format!("Changed files ({})", self.changes.len());
+        let list = List::new(items)
+            .block(Block::default().title(title).borders(Borders::ALL))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("> ");
+        frame.render_stateful_widget(list, files, &mut self.state);
+
+        let (title, lines) = match self.state.selected() {
+            Some(i) => (
+                self.changes[i].to_string(),
+                self.patches.get(&i).cloned().unwrap_or_default(),
+            ),
+            None => (
+                "diff".to_string(),
+                vec![Line::raw("There are no files with differences")],
+            ),
+        };
+        let diff_view = Paragraph::new(lines)
+            .block(Block::default().title(title).borders(Borders::ALL))
+            .scroll((self.scroll, 0));
+        frame.render_widget(diff_view, diff);
+
+        let help_text = Paragraph::new(
+            "Up/Down: file  j/k PageUp/PageDown: scroll  Enter: copy the files  Esc: back",
+        )
+        .block(Block::new().borders(Borders::ALL).title("Keys"));
+        frame.render_widget(help_text, help);
+    }
+}
+
+fn status_style(change: &FileChange) -> Style {
+    match change.status() {
+        'A' => Style::default().fg(Color::Green),
+        'D' => Style::default().fg(Color::Red),
+        'R' | 'C' => Style::default().fg(Color::Cyan),
+        _ => Style::default().fg(Color::Yellow),
+    }
+}
+
+/// Colors a unified diff the way `git diff --color` does
+fn colorize(patch: &str) -> Vec<Line<'static>> {
+    let mut in_header = false;
+    patch
+        .lines()
+        .map(|line| {
+            // Tabs are not expanded by the terminal backend
+            let text = line.replace('\t', "    ");
+            if line.starts_with("diff ") {
+                in_header = true;
+            } else if line.starts_with("@@") {
+                in_header = false;
+                return Line::styled(text, Style::default().fg(Color::Cyan));
+            }
+            let style = if in_header {
+                Style::default().add_modifier(Modifier::BOLD)
+            } else if line.starts_with('+') {
+                Style::default().fg(Color::Green)
+            } else if line.starts_with('-') {
+                Style::default().fg(Color::Red)
+            } else {
+                Style::default()
+            };
+            Line::styled(text, style)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use outdir_tempdir::TempDir;
+    use std::path::Path;
+
+    fn run_git(repo_dir: &Path, args: &[&str]) -> String {
+        let output = std::process::Command::new("git")
+            .args(args)
+            .current_dir(repo_dir)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "git {:?} failed: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout).unwrap().trim().to_string()
+    }
+
+    #[test]
+    fn preview_lists_only_the_files_the_copy_ships() {
+        let dir = TempDir::new().autorm();
+        let repo_dir = dir.path().join("repo");
+        std::fs::create_dir_all(&repo_dir).unwrap();
+        run_git(&repo_dir, &["init", "-q"]);
+        run_git(&repo_dir, &["config", "user.name", "Test"]);
+        run_git(&repo_dir, &["config", "user.email", "test@example.com"]);
+        std::fs::write(repo_dir.join(".gdeignore"), "secret.txt\n").unwrap();
+        std::fs::write(repo_dir.join("app.txt"), "v1\n").unwrap();
+        std::fs::write(repo_dir.join("secret.txt"), "v1\n").unwrap();
+        run_git(&repo_dir, &["add", "."]);
+        run_git(&repo_dir, &["commit", "-q", "-m", "first"]);
+        let from = run_git(&repo_dir, &["rev-parse", "HEAD"]);
+        std::fs::write(repo_dir.join("app.txt"), "v2\n").unwrap();
+        std::fs::write(repo_dir.join("secret.txt"), "v2\n").unwrap();
+        run_git(&repo_dir, &["commit", "-q", "-am", "second"]);
+        let to = run_git(&repo_dir, &["rev-parse", "HEAD"]);
+
+        let files_copy = FilesCopy::new("git", from, to, &repo_dir, dir.path().join("out"), "");
+        let preview = DiffPreview::new(&files_copy).unwrap();
+        assert_eq!(
+            vec![FileChange::Modified("app.txt".to_string())],
+            preview.changes
+        );
+    }
+
+    #[test]
+    fn colorize_styles_headers_hunks_and_changes() {
+        let lines = colorize(
+            "diff --git a/a.txt b/a.txt\n--- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-old\n+new\n\tsame\n",
+        );
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        assert_eq!(7, lines.len());
+        assert_eq!(bold, lines[1].style);
+        assert_eq!(bold, lines[2].style);
+        assert_eq!(Style::default().fg(Color::Cyan), lines[3].style);
+        assert_eq!(Style::default().fg(Color::Red), lines[4].style);
+        assert_eq!(Style::default().fg(Color::Green), lines[5].style);
+        assert_eq!(Style::default(), lines[6].style);
+        assert_eq!("    same", lines[6].spans[0].content);
+    }
+}
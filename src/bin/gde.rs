@@ -1,10 +1,17 @@
 use anyhow::Result;
-use clap::{Parser, Subcommand};
-use gde::{AutoCopy, FilesCopy};
+use clap::{Args, Parser, Subcommand};
+use gde::archive::ArchiveFormat;
+use gde::event::TextObserver;
+use gde::git::RefSource;
+use gde::{AutoCopy, CopyOptions, Extraction, FilesCopy};
+use serde::Serialize;
 use std::env;
-use std::io::stdout;
-use std::io::BufWriter;
+use std::fmt::Display;
+use std::io::{stderr, stdout, BufWriter, Write};
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::str::FromStr;
 
 #[derive(Debug, Parser)]
 struct Cli {
@@ -19,7 +26,7 @@ struct Cli {
     #[arg(long, value_name = "FROM COMMIT")]
     from: Option<String>,
 
-    /// Get all differences up to this commit
+    /// Get all differences up to this commit, or WORKTREE / INDEX for uncommitted changes
     #[arg(long, value_name = "TO COMMIT")]
     to: Option<String>,
 
@@ -27,14 +34,58 @@ struct Cli {
     #[arg(short, long, value_name = "OUTPUT DIR")]
     output: Option<PathBuf>,
 
+    #[command(flatten)]
+    copy: CopyArgs,
+
+    /// Print the files that would be written without writing anything
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// Output format: text, or json to print the --dry-run plan or the run report as JSON
+    #[arg(long, value_name = "FORMAT", default_value_t = OutputFormat::Text, global = true)]
+    format: OutputFormat,
+
     /// Path to the git-managed directory for diff
     #[arg(value_name = "TARGET REPO DIR")]
     target: Option<PathBuf>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("unknown format \"{s}\" (expected text or json)")),
+        }
+    }
+}
+
+impl Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Text => write!(f, "text"),
+            Self::Json => write!(f, "json"),
+        }
+    }
+}
+
 #[derive(Debug, Subcommand)]
 enum Commands {
-    Auto(AutoArgs),
+    Auto(Box<AutoArgs>),
+
+    /// Recompute the checksums of an output directory and report mismatched, missing and extra files
+    Verify(VerifyArgs),
+
+    /// Check that a deployed directory still matches the "from" files of an output directory
+    VerifyTarget(VerifyTargetArgs),
 }
 
 #[derive(Debug, Parser)]
@@ -51,19 +102,121 @@ struct AutoArgs {
     #[arg(short, long, value_name = "OUTPUT DIR")]
     output: PathBuf,
 
-    /// Branch name to exclude from processing
-    #[arg(long, value_name = "BRANCH NAME")]
+    /// Only process branches matching this glob, or regex with a re: prefix (repeatable)
+    #[arg(long, value_name = "BRANCH PATTERN")]
+    include: Vec<String>,
+
+    /// Skip branches matching this glob, or regex with a re: prefix (repeatable)
+    #[arg(long, value_name = "BRANCH PATTERN")]
     exclude: Vec<String>,
 
+    /// Refs listed as branches: local (default), remote, remote:<NAME>, tags or a refs/ pattern (repeatable)
+    #[arg(long, value_name = "SOURCE")]
+    refs: Vec<RefSource>,
+
     /// Append the branch head short hash to the output directory name
     #[arg(long)]
     output_with_short_hash: bool,
 
+    /// Number of branches processed at the same time
+    #[arg(short, long, default_value_t = NonZeroUsize::MIN, value_name = "N")]
+    jobs: NonZeroUsize,
+
+    #[command(flatten)]
+    copy: CopyArgs,
+
     /// Path to the git-managed directory for diff
     #[arg(value_name = "TARGET REPO DIR")]
     target: Option<PathBuf>,
 }
 
+#[derive(Debug, Parser)]
+struct VerifyArgs {
+    /// Output directory written with --checksums
+    #[arg(value_name = "OUTPUT DIR")]
+    output: PathBuf,
+}
+
+#[derive(Debug, Parser)]
+struct VerifyTargetArgs {
+    /// Output directory written by gde
+    #[arg(value_name = "OUTPUT DIR")]
+    output: PathBuf,
+
+    /// The deployed directory the "to" files will be copied over
+    #[arg(value_name = "TARGET DIR")]
+    target: PathBuf,
+
+    /// Treat CRLF and LF line endings as equal
+    #[arg(long)]
+    ignore_eol: bool,
+}
+
+#[derive(Debug, Args)]
+struct CopyArgs {
+    /// Only package changed files matching this glob (repeatable)
+    #[arg(long, value_name = "GLOB")]
+    include_path: Vec<String>,
+
+    /// Do not package changed files matching this glob (repeatable, added to .gdeignore)
+    #[arg(long, value_name = "GLOB")]
+    exclude_path: Vec<String>,
+
+    /// Limit the changed files with a raw git pathspec (repeatable)
+    #[arg(long, value_name = "PATHSPEC")]
+    pathspec: Vec<String>,
+
+    /// How changed files are read: worktree (check out both commits) or cat-file (read only the changed blobs)
+    #[arg(long, value_name = "METHOD", default_value_t = Extraction::Worktree)]
+    extract: Extraction,
+
+    /// Also write a unified diff for each changed file and a combined changes.patch
+    #[arg(long)]
+    patches: bool,
+
+    /// Also write apply.sh and apply.ps1 that copy the "to" files over a target directory
+    #[arg(long)]
+    scripts: bool,
+
+    /// Also write rollback.sh and rollback.ps1 that restore the "from" files in a target directory
+    #[arg(long)]
+    rollback: bool,
+
+    /// Also write SHA256SUMS-style checksums that can be checked with `gde verify`
+    #[arg(long)]
+    checksums: bool,
+
+    /// Three-way merge the files this deployed directory has changed into merged/
+    #[arg(long, value_name = "TARGET DIR")]
+    merge_with: Option<PathBuf>,
+
+    /// Also package the output into an archive (zip or tar.gz)
+    #[arg(long, value_name = "FORMAT")]
+    archive: Option<ArchiveFormat>,
+
+    /// Write only the archive instead of the output directory
+    #[arg(long, requires = "archive")]
+    archive_only: bool,
+}
+
+impl From<CopyArgs> for CopyOptions {
+    fn from(value: CopyArgs) -> Self {
+        Self {
+            include_paths: value.include_path,
+            exclude_paths: value.exclude_path,
+            pathspecs: value.pathspec,
+            extraction: value.extract,
+            patches: value.patches,
+            scripts: value.scripts,
+            rollback: value.rollback,
+            checksums: value.checksums,
+            merge_with: value.merge_with,
+            archive: value.archive,
+            archive_only: value.archive_only,
+        }
+    }
+}
+
 fn absolute_path(path: impl AsRef<Path>) -> Result<PathBuf> {
     let path = path.as_ref();
     let ret = if path.is_absolute() {
@@ -75,19 +228,120 @@ fn absolute_path(path: impl AsRef<Path>) -> Result<PathBuf> {
     Ok(ret)
 }
 
-fn main() -> Result<()> {
+fn write_json<W: Write, T: Serialize>(w: &mut W, value: &T) -> Result<()> {
+    writeln!(w, "{}", serde_json::to_string_pretty(value)?)?;
+    w.flush()?;
+    Ok(())
+}
+
+fn verify_package(args: &VerifyArgs) -> Result<()> {
+    let output_dir = absolute_path(&args.output)?;
+    println!("Verifying: {}", output_dir.display());
+    let report = gde::checksum::verify(&output_dir)?;
+    for path in report.mismatched.iter() {
+        println!("Mismatched: {path}");
+    }
+    for path in report.missing.iter() {
+        println!("Missing: {path}");
+    }
+    for path in report.extra.iter() {
+        println!("Extra: {path}");
+    }
+
+    if !report.is_ok() {
+        anyhow::bail!(
+            "Verification failed ({} mismatched, {} missing, {} extra)",
+            report.mismatched.len(),
+            report.missing.len(),
+            report.extra.len()
+        );
+    }
+    println!("OK");
+    Ok(())
+}
+
+fn verify_target(args: &VerifyTargetArgs) -> Result<()> {
+    let output_dir = absolute_path(&args.output)?;
+    let target_dir = absolute_path(&args.target)?;
+    println!("Output directory: {}", output_dir.display());
+    println!("Target directory: {}", target_dir.display());
+    let report = gde::drift::check_target(&output_dir, &target_dir, args.ignore_eol)?;
+    for path in report.modified.iter() {
+        println!("Modified: {path}");
+    }
+    for path in report.missing.iter() {
+        println!("Missing: {path}");
+    }
+    for path in report.existing.iter() {
+        println!("Already exists: {path}");
+    }
+
+    if !report.is_ok() {
+        anyhow::bail!(
+            "The target directory has drifted from the \"from\" files ({} modified, {} missing, {} already existing)",
+            report.modified.len(),
+            report.missing.len(),
+            report.existing.len()
+        );
+    }
+    println!("OK");
+    Ok(())
+}
+
+/// Returns the exit status of a failed run, so scripts can tell the failures apart
+fn exit_code(e: &anyhow::Error) -> u8 {
+    let git_error = match e.downcast_ref::<gde::Error>() {
+        Some(gde::Error::OutputDirNotWritable { .. }) => return 8,
+        Some(e) => e.git(),
+        None => e.downcast_ref::<gde::git::Error>(),
+    };
+    match git_error {
+        Some(gde::git::Error::GitNotFound { .. }) => 3,
+        Some(gde::git::Error::NotARepository { .. }) => 4,
+        Some(gde::git::Error::UnknownRevision { .. }) => 5,
+        Some(gde::git::Error::WorktreeAdd { .. }) => 6,
+        Some(gde::git::Error::DirtyPathConflict { .. }) => 7,
+        _ => 1,
+    }
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {e:?}");
+            ExitCode::from(exit_code(&e))
+        }
+    }
+}
+
+fn run() -> Result<()> {
     let cli = Cli::parse();
+    // The receiving side does not need git to check a package
+    match &cli.command {
+        Some(Commands::Verify(args)) => return verify_package(args),
+        Some(Commands::VerifyTarget(args)) => return verify_target(args),
+        _ => (),
+    }
+
     let git_path = if let Some(git) = cli.git {
         git.display().to_string()
     } else {
         "git".to_string()
     };
 
-    let git = gde::git::Git::from_path(&git_path)?;
-    println!("Git version: {}", git.version());
-
     let out = stdout();
     let mut out = BufWriter::new(out.lock());
+    // With --format json the plan or report is the only thing written to stdout
+    let json = cli.format == OutputFormat::Json;
+    let mut info: Box<dyn Write> = if json {
+        Box::new(stderr())
+    } else {
+        Box::new(stdout())
+    };
+
+    let git = gde::git::Git::from_path(&git_path)?;
+    writeln!(info, "Git version: {}", git.version())?;
     match cli.command {
         None => {
             let from = cli
@@ -101,23 +355,38 @@ fn main() -> Result<()> {
             } else {
                 env::current_dir()?
             };
-            println!("Target directory: {}", target_dir.display());
-            println!(
+            writeln!(info, "Target directory: {}", target_dir.display())?;
+            writeln!(
+                info,
                 "Root directory: {}",
                 git.get_rootdir(&target_dir)?.display()
-            );
+            )?;
             let output_dir = if let Some(dir) = cli.output {
                 absolute_path(dir)?
             } else {
                 env::current_dir()?.join(format!("gde-{}", uuid::Uuid::new_v4()))
             };
-            println!("Output directory: {}", output_dir.display());
+            writeln!(info, "Output directory: {}", output_dir.display())?;
 
             let current_commit = git.get_hash(&target_dir, "HEAD")?;
-            println!("Current commit: {}", current_commit);
+            writeln!(info, "Current commit: {}", current_commit)?;
 
-            let f = FilesCopy::new(git_path, from, to, target_dir, output_dir, current_commit);
-            f.copy(&mut out)?;
+            let f = FilesCopy::new(git_path, from, to, target_dir, output_dir, current_commit)
+                .with_options(cli.copy.into());
+            if cli.dry_run {
+                let plan = f.plan()?;
+                if json {
+                    write_json(&mut out, &plan)?;
+                } else {
+                    writeln!(out, "Dry run: nothing is written")?;
+                    plan.write_text(&mut out)?;
+                }
+            } else if json {
+                let report = f.copy(&mut TextObserver::new(&mut info))?;
+                write_json(&mut out, &report)?;
+            } else {
+                f.copy(&mut TextObserver::new(&mut out))?;
+            }
         }
         Some(Commands::Auto(auto)) => {
             let target_dir = if let Some(dir) = auto.target {
@@ -125,13 +394,14 @@ fn main() -> Result<()> {
             } else {
                 env::current_dir()?
             };
-            println!("Target directory: {}", target_dir.display());
-            println!(
+            writeln!(info, "Target directory: {}", target_dir.display())?;
+            writeln!(
+                info,
                 "Root directory: {}",
                 git.get_rootdir(&target_dir)?.display()
-            );
+            )?;
             let output_dir = absolute_path(auto.output)?;
-            println!("Output directory: {}", output_dir.display());
+            writeln!(info, "Output directory: {}", output_dir.display())?;
 
             let auto_copy = AutoCopy::new(
                 git_path,
@@ -141,9 +411,32 @@ fn main() -> Result<()> {
                 output_dir,
                 auto.exclude,
                 auto.output_with_short_hash,
-            );
-            auto_copy.copy(&mut out)?;
+            )
+            .with_options(auto.copy.into())
+            .with_jobs(auto.jobs.get())
+            .with_ref_sources(auto.refs)
+            .with_includes(auto.include);
+            if cli.dry_run {
+                let plan = auto_copy.plan()?;
+                if json {
+                    write_json(&mut out, &plan)?;
+                } else {
+                    writeln!(out, "Dry run: nothing is written")?;
+                    plan.write_text(&mut out)?;
+                }
+            } else if json {
+                // The report is printed even when some branches failed
+                let report = auto_copy.run(&mut TextObserver::new(&mut info))?;
+                write_json(&mut out, &report)?;
+                let failed = report.failed().len();
+                if failed > 0 {
+                    anyhow::bail!("{} of {} branches failed", failed, report.branches.len());
+                }
+            } else {
+                auto_copy.copy(&mut TextObserver::new(&mut out))?;
+            }
         }
+        Some(Commands::Verify(_)) | Some(Commands::VerifyTarget(_)) => unreachable!(),
     }
 
     Ok(())
@@ -166,6 +459,70 @@ mod tests {
         assert_eq!(cli.to, Some("def456".to_string()));
         assert_eq!(cli.output, Some(PathBuf::from("out")));
         assert_eq!(cli.target, Some(PathBuf::from(".")));
+        assert!(!cli.copy.patches);
+        assert_eq!(cli.copy.extract, Extraction::Worktree);
+        assert_eq!(cli.copy.archive, None);
+    }
+
+    #[test]
+    fn cli_supports_output_options_in_both_modes() {
+        let cli = Cli::try_parse_from([
+            "gde",
+            "--from",
+            "abc123",
+            "--to",
+            "def456",
+            "--patches",
+            "--scripts",
+            "--rollback",
+            "--merge-with",
+            "/srv/app",
+            "--include-path",
+            "src/**",
+            "--exclude-path",
+            "docs",
+            "--exclude-path",
+            "*.md",
+            "--pathspec",
+            ":(icase)readme",
+            "--extract",
+            "cat-file",
+        ])
+        .unwrap();
+        assert_eq!(cli.copy.extract, Extraction::CatFile);
+        assert_eq!(cli.copy.include_path, vec!["src/**".to_string()]);
+        assert_eq!(
+            cli.copy.exclude_path,
+            vec!["docs".to_string(), "*.md".to_string()]
+        );
+        assert_eq!(cli.copy.pathspec, vec![":(icase)readme".to_string()]);
+        assert!(cli.copy.patches);
+        assert_eq!(cli.copy.merge_with, Some(PathBuf::from("/srv/app")));
+        assert!(cli.copy.scripts);
+        assert!(cli.copy.rollback);
+
+        let cli = Cli::try_parse_from([
+            "gde",
+            "auto",
+            "--from",
+            "abc123",
+            "-o",
+            "out",
+            "--patches",
+            "--exclude",
+            "main",
+            "--exclude-path",
+            "docs",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::Auto(auto)) => {
+                assert!(auto.copy.patches);
+                assert_eq!(auto.exclude, vec!["main".to_string()]);
+                assert_eq!(auto.copy.exclude_path, vec!["docs".to_string()]);
+            }
+            _ => panic!("auto command was not parsed"),
+        }
     }
 
     #[test]
@@ -183,7 +540,15 @@ mod tests {
             "main",
             "--exclude",
             "master",
+            "--include",
+            "feature/*",
             "--output-with-short-hash",
+            "--jobs",
+            "4",
+            "--refs",
+            "local",
+            "--refs",
+            "remote:upstream",
             ".",
         ])
         .unwrap();
@@ -194,13 +559,138 @@ mod tests {
                 assert_eq!(auto.days, 15);
                 assert_eq!(auto.output, PathBuf::from("out"));
                 assert_eq!(auto.exclude, vec!["main".to_string(), "master".to_string()]);
+                assert_eq!(auto.include, vec!["feature/*".to_string()]);
                 assert!(auto.output_with_short_hash);
+                assert_eq!(auto.jobs.get(), 4);
+                assert_eq!(
+                    auto.refs,
+                    vec![RefSource::Local, RefSource::Remote("upstream".to_string())]
+                );
                 assert_eq!(auto.target, Some(PathBuf::from(".")));
             }
-            None => panic!("auto command was not parsed"),
+            _ => panic!("auto command was not parsed"),
+        }
+
+        assert!(
+            Cli::try_parse_from(["gde", "auto", "--from", "a", "-o", "out", "--jobs", "0"])
+                .is_err()
+        );
+        assert!(Cli::try_parse_from([
+            "gde", "auto", "--from", "a", "-o", "out", "--refs", "heads"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn cli_supports_archive_options() {
+        let cli = Cli::try_parse_from([
+            "gde",
+            "--from",
+            "abc123",
+            "--to",
+            "def456",
+            "--archive",
+            "tar.gz",
+            "--archive-only",
+        ])
+        .unwrap();
+        assert_eq!(cli.copy.archive, Some(ArchiveFormat::TarGz));
+        assert!(cli.copy.archive_only);
+
+        let cli = Cli::try_parse_from([
+            "gde",
+            "auto",
+            "--from",
+            "abc123",
+            "-o",
+            "out",
+            "--archive",
+            "zip",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Commands::Auto(auto)) => assert_eq!(auto.copy.archive, Some(ArchiveFormat::Zip)),
+            _ => panic!("auto command was not parsed"),
+        }
+
+        assert!(
+            Cli::try_parse_from(["gde", "--from", "a", "--to", "b", "--archive-only"]).is_err()
+        );
+        assert!(
+            Cli::try_parse_from(["gde", "--from", "a", "--to", "b", "--archive", "rar"]).is_err()
+        );
+    }
+
+    #[test]
+    fn cli_supports_verify_subcommands() {
+        let cli = Cli::try_parse_from(["gde", "verify", "out"]).unwrap();
+        match cli.command {
+            Some(Commands::Verify(verify)) => assert_eq!(verify.output, PathBuf::from("out")),
+            _ => panic!("verify command was not parsed"),
+        }
+
+        let cli = Cli::try_parse_from(["gde", "verify-target", "out", "/srv/app", "--ignore-eol"])
+            .unwrap();
+        match cli.command {
+            Some(Commands::VerifyTarget(verify)) => {
+                assert_eq!(verify.output, PathBuf::from("out"));
+                assert_eq!(verify.target, PathBuf::from("/srv/app"));
+                assert!(verify.ignore_eol);
+            }
+            _ => panic!("verify-target command was not parsed"),
         }
     }
 
+    #[test]
+    fn cli_supports_dry_run_in_both_modes() {
+        let cli = Cli::try_parse_from(["gde", "--from", "a", "--to", "b", "--dry-run"]).unwrap();
+        assert!(cli.dry_run);
+        assert_eq!(cli.format, OutputFormat::Text);
+
+        let cli = Cli::try_parse_from([
+            "gde",
+            "auto",
+            "--from",
+            "a",
+            "-o",
+            "out",
+            "--dry-run",
+            "--format",
+            "json",
+        ])
+        .unwrap();
+        assert!(cli.dry_run);
+        assert_eq!(cli.format, OutputFormat::Json);
+        assert!(matches!(cli.command, Some(Commands::Auto(_))));
+
+        assert!(Cli::try_parse_from(["gde", "--dry-run", "--format", "yaml"]).is_err());
+    }
+
+    #[test]
+    fn exit_code_tells_failures_apart() {
+        let e = anyhow::Error::from(gde::git::Git::get_version("/no/such/git").unwrap_err());
+        assert_eq!(3, exit_code(&e));
+
+        // A git that cannot be run is not reported as missing
+        #[cfg(unix)]
+        {
+            let dir = outdir_tempdir::TempDir::new().autorm();
+            let not_executable = dir.path().join("git");
+            std::fs::write(&not_executable, "").unwrap();
+            let e = gde::git::Git::get_version(&not_executable).unwrap_err();
+            assert!(!matches!(e, gde::git::Error::GitNotFound { .. }), "{e}");
+            assert_eq!(1, exit_code(&anyhow::Error::from(e)));
+        }
+
+        let e = anyhow::Error::from(gde::Error::OutputDirNotWritable {
+            path: PathBuf::from("out"),
+            source: std::io::ErrorKind::PermissionDenied.into(),
+        });
+        assert_eq!(8, exit_code(&e));
+
+        assert_eq!(1, exit_code(&anyhow::anyhow!("something else")));
+    }
+
     #[test]
     fn cli_command_structure_is_valid() {
         Cli::command().debug_assert();
@@ -0,0 +1,240 @@
+use crate::report::REPORT_FILE;
+use anyhow::{anyhow, Result};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// File name of the top-level digest of everything outside the `from/` and `to/` trees
+pub const SHA256SUMS: &str = "SHA256SUMS";
+
+/// The trees that get their own `<side>.sha256` list
+const SIDES: [&str; 2] = ["from", "to"];
+
+/// Returns the lowercase hex SHA-256 digest of a file
+pub fn sha256_file(path: impl AsRef<Path>) -> Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path.as_ref())?, &mut hasher)?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|x| format!("{x:02x}"))
+        .collect())
+}
+
+/// Writes `from.sha256` and `to.sha256` for the two trees and a top-level `SHA256SUMS` covering
+/// those lists and every other file in the output directory.
+/// All lists use the `sha256sum` format, so `cd from && sha256sum -c ../from.sha256` also works.
+pub fn write_checksums(output_dir: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+    let output_dir = output_dir.as_ref();
+    let mut written = Vec::new();
+    for side in SIDES {
+        let dir = output_dir.join(side);
+        if !dir.is_dir() {
+            continue;
+        }
+        let list_file = output_dir.join(format!("{side}.sha256"));
+        fs::write(&list_file, format_list(&dir, &crate::list_files(&dir)?)?)?;
+        written.push(list_file);
+    }
+
+    let sums_file = output_dir.join(SHA256SUMS);
+    fs::write(
+        &sums_file,
+        format_list(output_dir, &top_level_files(output_dir)?)?,
+    )?;
+    written.push(sums_file);
+    Ok(written)
+}
+
+/// Files that differ from the checksums written by [`write_checksums`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// Files whose contents do not match their checksum
+    pub mismatched: Vec<String>,
+
+    /// Files listed in a checksum file that do not exist
+    pub missing: Vec<String>,
+
+    /// Files that exist but are not listed in any checksum file
+    pub extra: Vec<String>,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.mismatched.is_empty() && self.missing.is_empty() && self.extra.is_empty()
+    }
+}
+
+/// Recomputes the checksums of an output directory. Paths in the report are relative to it.
+pub fn verify(output_dir: impl AsRef<Path>) -> Result<VerifyReport> {
+    let output_dir = output_dir.as_ref();
+    let sums_file = output_dir.join(SHA256SUMS);
+    if !sums_file.is_file() {
+        return Err(anyhow!("{} does not exist", sums_file.display()));
+    }
+
+    let mut report = VerifyReport::default();
+    verify_list(
+        output_dir,
+        "",
+        &parse_list(&fs::read_to_string(&sums_file)?)?,
+        &top_level_files(output_dir)?,
+        &mut report,
+    )?;
+    for side in SIDES {
+        let list_file = output_dir.join(format!("{side}.sha256"));
+        if !list_file.is_file() {
+            continue;
+        }
+        let dir = output_dir.join(side);
+        let actual = if dir.is_dir() {
+            crate::list_files(&dir)?
+        } else {
+            Vec::new()
+        };
+        verify_list(
+            &dir,
+            &format!("{side}/"),
+            &parse_list(&fs::read_to_string(&list_file)?)?,
+            &actual,
+            &mut report,
+        )?;
+    }
+
+    Ok(report)
+}
+
+/// Every file in the output directory outside the `from/` and `to/` trees, except `SHA256SUMS`
+/// and the run report
+fn top_level_files(output_dir: &Path) -> Result<Vec<String>> {
+    Ok(crate::list_files(output_dir)?
+        .into_iter()
+        .filter(|x| x != SHA256SUMS && x != REPORT_FILE)
+        .filter(|x| !SIDES.iter().any(|side| x.starts_with(&format!("{side}/"))))
+        .collect())
+}
+
+fn format_list(dir: &Path, files: &[String]) -> Result<String> {
+    let mut s = String::new();
+    for file in files.iter() {
+        s.push_str(&format!("{}  {file}\n", sha256_file(dir.join(file))?));
+    }
+    Ok(s)
+}
+
+/// Parse the `sha256sum` format ("<digest>  <path>" or "<digest> *<path>")
+fn parse_list(s: &str) -> Result<BTreeMap<String, String>> {
+    s.lines()
+        .filter(|x| !x.is_empty())
+        .map(|line| {
+            let (digest, path) = line
+                .split_once(' ')
+                .ok_or_else(|| anyhow!("Could not parse \"{line}\""))?;
+            let path = path
+                .strip_prefix(' ')
+                .or_else(|| path.strip_prefix('*'))
+                .ok_or_else(|| anyhow!("Could not parse \"{line}\""))?;
+            Ok((path.to_string(), digest.to_lowercase()))
+        })
+        .collect()
+}
+
+fn verify_list(
+    dir: &Path,
+    prefix: &str,
+    expected: &BTreeMap<String, String>,
+    actual: &[String],
+    report: &mut VerifyReport,
+) -> Result<()> {
+    for (path, digest) in expected.iter() {
+        let file = dir.join(path);
+        if !file.is_file() {
+            report.missing.push(format!("{prefix}{path}"));
+        } else if sha256_file(&file)? != *digest {
+            report.mismatched.push(format!("{prefix}{path}"));
+        }
+    }
+    for path in actual.iter().filter(|x| !expected.contains_key(*x)) {
+        report.extra.push(format!("{prefix}{path}"));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use outdir_tempdir::TempDir;
+
+    fn write(path: impl AsRef<Path>, bytes: &[u8]) {
+        let path = path.as_ref();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, bytes).unwrap();
+    }
+
+    fn package() -> TempDir {
+        let dir = TempDir::new().autorm();
+        write(dir.path().join("from").join("a.txt"), b"a\n");
+        write(dir.path().join("to").join("a.txt"), b"b\n");
+        write(dir.path().join("to").join("nested").join("c.txt"), b"c\n");
+        write(dir.path().join("manifest.json"), b"{}\n");
+        write_checksums(dir.path()).unwrap();
+        dir
+    }
+
+    #[test]
+    fn sha256_file_matches_known_digest() {
+        let dir = TempDir::new().autorm();
+        write(dir.path().join("abc"), b"abc");
+        assert_eq!(
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            sha256_file(dir.path().join("abc")).unwrap()
+        );
+    }
+
+    #[test]
+    fn write_checksums_uses_sha256sum_format() {
+        let dir = package();
+
+        let to = fs::read_to_string(dir.path().join("to.sha256")).unwrap();
+        let lines = to.lines().collect::<Vec<_>>();
+        assert_eq!(2, lines.len());
+        assert!(lines[0].ends_with("  a.txt"));
+        assert!(lines[1].ends_with("  nested/c.txt"));
+
+        let sums = fs::read_to_string(dir.path().join(SHA256SUMS)).unwrap();
+        assert!(sums.contains("  from.sha256\n"));
+        assert!(sums.contains("  to.sha256\n"));
+        assert!(sums.contains("  manifest.json\n"));
+        assert!(!sums.contains("a.txt"));
+    }
+
+    #[test]
+    fn verify_accepts_untouched_package() {
+        let dir = package();
+        assert_eq!(VerifyReport::default(), verify(dir.path()).unwrap());
+    }
+
+    #[test]
+    fn verify_reports_mismatched_missing_and_extra_files() {
+        let dir = package();
+        write(dir.path().join("to").join("a.txt"), b"tampered\n");
+        fs::remove_file(dir.path().join("to").join("nested").join("c.txt")).unwrap();
+        write(dir.path().join("from").join("extra.txt"), b"extra\n");
+        write(dir.path().join("notes.txt"), b"extra\n");
+
+        let report = verify(dir.path()).unwrap();
+        assert!(!report.is_ok());
+        assert_eq!(vec!["to/a.txt"], report.mismatched);
+        assert_eq!(vec!["to/nested/c.txt"], report.missing);
+        assert_eq!(vec!["notes.txt", "from/extra.txt"], report.extra);
+    }
+
+    #[test]
+    fn verify_fails_without_checksums() {
+        let dir = TempDir::new().autorm();
+        assert!(verify(dir.path()).is_err());
+    }
+}
@@ -1,8 +1,121 @@
-use crate::git::{GitDiff, GitWorktree};
+use crate::archive::{self, ArchiveFormat};
+use crate::checksum;
+use crate::event::{CopySource, Event, Observer};
+use crate::git::{
+    FileChange, Git, GitCatFile, GitCheckoutIndex, GitDiff, GitLsFiles, GitReadTree, GitRevision,
+    GitWorktree,
+};
+use crate::manifest::{Manifest, DELETED_FILE, MANIFEST_FILE};
+use crate::merge;
+use crate::pathspec;
+use crate::plan::CopyPlan;
+use crate::report::{CopyReport, FileDigest, FileReport, Stopwatch, REPORT_FILE};
+use crate::script::ScriptPlan;
+use crate::Defer;
 use anyhow::Result;
+use itertools::Itertools;
+use std::collections::HashSet;
+use std::fmt::Display;
 use std::fs;
-use std::io::Write;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+/// `to` value that packages the current working tree instead of a commit
+pub const WORKTREE: &str = "WORKTREE";
+
+/// `to` value that packages the staged contents instead of a commit
+pub const INDEX: &str = "INDEX";
+
+/// Where the "to" files are taken from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ToSource {
+    Commit,
+    Worktree,
+    Index,
+}
+
+impl ToSource {
+    fn of(to: &str) -> Self {
+        match to {
+            WORKTREE => Self::Worktree,
+            INDEX => Self::Index,
+            _ => Self::Commit,
+        }
+    }
+}
+
+/// How the changed files are read from the repository
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Extraction {
+    /// Check out both commits into temporary worktrees and copy the files from there
+    #[default]
+    Worktree,
+
+    /// Stream only the changed blobs through `git cat-file`, applying the same filters as a
+    /// checkout
+    CatFile,
+}
+
+impl FromStr for Extraction {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "worktree" => Ok(Self::Worktree),
+            "cat-file" => Ok(Self::CatFile),
+            _ => Err(format!(
+                "unknown extraction \"{s}\" (expected worktree or cat-file)"
+            )),
+        }
+    }
+}
+
+impl Display for Extraction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Worktree => write!(f, "worktree"),
+            Self::CatFile => write!(f, "cat-file"),
+        }
+    }
+}
+
+/// Optional outputs written alongside the `from` and `to` trees
+#[derive(Debug, Clone, Default)]
+pub struct CopyOptions {
+    /// Only package changed files matching these globs
+    pub include_paths: Vec<String>,
+
+    /// Do not package changed files matching these globs (in addition to `.gdeignore`)
+    pub exclude_paths: Vec<String>,
+
+    /// Raw git pathspecs that limit the changed files
+    pub pathspecs: Vec<String>,
+
+    /// How the changed files are read from the repository
+    pub extraction: Extraction,
+
+    /// Write a unified diff for each changed file under `patches/` and a combined `changes.patch`
+    pub patches: bool,
+
+    /// Write `apply.sh` and `apply.ps1` that copy `to/` over a target directory in the `from` state
+    pub scripts: bool,
+
+    /// Write `rollback.sh` and `rollback.ps1` that restore a target directory in the `to` state
+    pub rollback: bool,
+
+    /// Write `SHA256SUMS`-style checksum lists for the package
+    pub checksums: bool,
+
+    /// Three-way merge the files this deployed directory has changed since the "from" commit
+    /// into `merged/` and write `merge-summary.txt`
+    pub merge_with: Option<PathBuf>,
+
+    /// Also package the output into `<output dir>.zip` or `<output dir>.tar.gz`
+    pub archive: Option<ArchiveFormat>,
+
+    /// Write only the archive and no output directory (requires `archive`)
+    pub archive_only: bool,
+}
 
 /// Provides a feature to copy the differential files between two specified commits
 pub struct FilesCopy {
@@ -23,6 +136,9 @@ pub struct FilesCopy {
 
     /// The current commit in the target directory
     current_commit: String,
+
+    /// Optional outputs
+    options: CopyOptions,
 }
 
 impl FilesCopy {
@@ -41,72 +157,623 @@ impl FilesCopy {
             target_dir: target_dir.into(),
             output_dir: output_dir.into(),
             current_commit: current_commit.into(),
+            options: CopyOptions::default(),
+        }
+    }
+
+    /// The directory the files are written to
+    pub fn output_dir(&self) -> &Path {
+        &self.output_dir
+    }
+
+    /// Sets the optional outputs to write alongside the `from` and `to` trees
+    pub fn with_options(mut self, options: CopyOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Resolves the commits and the changed files and returns every file [`FilesCopy::copy`]
+    /// would write, without creating worktrees or touching the output directory
+    pub fn plan(&self) -> crate::Result<CopyPlan> {
+        let git = Git::from_path(&self.git_path)?;
+        let root_dir = git.get_rootdir(&self.target_dir)?;
+        let (from, to) = self.resolve(&git, &root_dir)?;
+        let (_, changes) = self.diff(&root_dir)?;
+        let manifest = Manifest::new(from, to, &changes);
+        let merged = match self.options.merge_with.as_ref() {
+            Some(target_dir) if !changes.is_empty() => {
+                self.planned_merges(&root_dir, &changes, &manifest, target_dir)?
+            }
+            _ => Vec::new(),
+        };
+        let writes = self.planned_writes(&manifest, &merged);
+        Ok(CopyPlan {
+            from: manifest.from,
+            to: manifest.to,
+            output_dir: self.output_dir.clone(),
+            files: manifest.files,
+            writes,
+        })
+    }
+
+    /// The diff of the files [`FilesCopy::copy`] ships, with the path filters and `.gdeignore`
+    /// applied, and its changed files
+    pub fn changes(&self) -> crate::Result<(GitDiff, Vec<FileChange>)> {
+        let git = Git::from_path(&self.git_path)?;
+        let root_dir = git.get_rootdir(&self.target_dir)?;
+        Ok(self.diff(&root_dir)?)
+    }
+
+    /// Copies the differential files between the commits specified in the constructor and
+    /// returns a record of the run, which is also written to `report.json`
+    pub fn copy<O: Observer + ?Sized>(&self, observer: &mut O) -> crate::Result<CopyReport> {
+        let mut stopwatch = Stopwatch::start();
+        let git = Git::from_path(&self.git_path)?;
+        let root_dir = git.get_rootdir(&self.target_dir)?;
+        let (from, to) = self.resolve(&git, &root_dir)?;
+        let to_source = ToSource::of(&self.to_commit);
+        let (gitdiff, changes) = self.diff(&root_dir)?;
+        stopwatch.lap("diff");
+        let mut report = CopyReport {
+            git_version: git.version().to_string(),
+            from,
+            to,
+            output_dir: self.output_dir.clone(),
+            archive: None,
+            started_at: stopwatch.started_at(),
+            duration_ms: 0,
+            timings: Vec::new(),
+            files: Vec::new(),
+        };
+        observer.notify(Event::Started {
+            from: self.from_commit.clone(),
+            to: self.to_commit.clone(),
+            changes: changes.clone(),
+        })?;
+        if changes.is_empty() {
+            report.duration_ms = stopwatch.elapsed_ms();
+            report.timings = stopwatch.timings();
+            observer.notify(Event::Finished {
+                duration_ms: report.duration_ms,
+            })?;
+            return Ok(report);
+        }
+
+        // With `archive_only` everything is staged in a temporary directory and packed from there
+        let archive_only = self.options.archive.is_some() && self.options.archive_only;
+        let staging_dir = std::env::temp_dir()
+            .join("gde-staging")
+            .join(uuid::Uuid::new_v4().to_string());
+        let _staging_guard = archive_only.then(|| {
+            Defer::new(|| {
+                let _ = fs::remove_dir_all(&staging_dir);
+            })
+        });
+        let output_dir = if archive_only {
+            staging_dir.as_path()
+        } else {
+            self.output_dir.as_path()
+        };
+
+        crate::create_output_dir(output_dir)?;
+        // Kept for API compatibility with the previous checkout/reset implementation.
+        let _ = &self.current_commit;
+
+        let from_files = changes
+            .iter()
+            .filter_map(|x| x.from_path().map(Into::into))
+            .collect::<Vec<String>>();
+        let to_files = changes
+            .iter()
+            .filter_map(|x| x.to_path().map(Into::into))
+            .collect::<Vec<String>>();
+        let from_dir = output_dir.join("from");
+        let to_dir = output_dir.join("to");
+        match self.options.extraction {
+            Extraction::Worktree => self.extract_with_worktrees(
+                to_source,
+                &root_dir,
+                (&from_files, &from_dir),
+                (&to_files, &to_dir),
+                observer,
+            )?,
+            Extraction::CatFile => self.extract_with_cat_file(
+                to_source,
+                &root_dir,
+                (&from_files, &from_dir),
+                (&to_files, &to_dir),
+                observer,
+            )?,
+        }
+        stopwatch.lap("extract");
+
+        // Tell the receiving side which files to remove
+        let manifest = Manifest::new(&self.from_commit, &self.to_commit, &changes);
+        for path in manifest.write(output_dir)? {
+            observer.notify(Event::FileWritten { path })?;
+        }
+        stopwatch.lap("manifest");
+
+        if let Some(merge_with) = self.options.merge_with.as_ref() {
+            observer.notify(Event::MergeStarted {
+                target: merge_with.clone(),
+            })?;
+            let summary = merge::merge_target(
+                &self.git_path,
+                &self.target_dir,
+                output_dir,
+                &manifest,
+                merge_with,
+            )?;
+            for entry in summary.entries.iter() {
+                observer.notify(Event::FileMerged {
+                    entry: entry.clone(),
+                })?;
+            }
+            observer.notify(Event::FileWritten {
+                path: summary.write(output_dir)?,
+            })?;
+            observer.notify(Event::MergeFinished {
+                unresolved: summary.unresolved().len(),
+            })?;
+            stopwatch.lap("merge");
         }
+
+        if self.options.scripts {
+            for path in ScriptPlan::apply(&manifest).write(output_dir, "apply")? {
+                observer.notify(Event::FileWritten { path })?;
+            }
+        }
+
+        if self.options.rollback {
+            for path in ScriptPlan::rollback(&manifest).write(output_dir, "rollback")? {
+                observer.notify(Event::FileWritten { path })?;
+            }
+        }
+        if self.options.scripts || self.options.rollback {
+            stopwatch.lap("scripts");
+        }
+
+        if self.options.patches {
+            observer.notify(Event::PatchesStarted)?;
+            Self::write_patches(&gitdiff, &changes, output_dir, observer)?;
+            stopwatch.lap("patches");
+        }
+
+        if self.options.checksums {
+            for path in checksum::write_checksums(output_dir)? {
+                observer.notify(Event::FileWritten { path })?;
+            }
+            stopwatch.lap("checksums");
+        }
+
+        if let Some(format) = self.options.archive {
+            let archive_file = format.archive_path(&self.output_dir);
+            observer.notify(Event::ArchiveStarted {
+                path: archive_file.clone(),
+            })?;
+            self.write_archive(format, output_dir, &archive_file)?;
+            observer.notify(Event::ArchiveWritten {
+                path: archive_file.clone(),
+            })?;
+            report.archive = Some(archive_file);
+            stopwatch.lap("archive");
+        }
+
+        // The report records the run and is not part of the checksummed or archived package
+        for entry in manifest.files {
+            let from = match entry.path_at_from() {
+                Some(path) => FileDigest::of(from_dir.join(path))?,
+                None => None,
+            };
+            let to = match entry.path_at_to() {
+                Some(path) => FileDigest::of(to_dir.join(path))?,
+                None => None,
+            };
+            report.files.push(FileReport { entry, from, to });
+        }
+        stopwatch.lap("report");
+        report.duration_ms = stopwatch.elapsed_ms();
+        report.timings = stopwatch.timings();
+        let report_file = self.report_file();
+        report.write(&report_file)?;
+        observer.notify(Event::FileWritten { path: report_file })?;
+        observer.notify(Event::Finished {
+            duration_ms: report.duration_ms,
+        })?;
+        Ok(report)
+    }
+
+    /// Resolves the "from" and "to" commits to hashes. `WORKTREE` and `INDEX` are kept as they are.
+    fn resolve(&self, git: &Git, root_dir: &Path) -> Result<(String, String)> {
+        let from = git.get_hash(root_dir, &self.from_commit)?;
+        let to = match ToSource::of(&self.to_commit) {
+            ToSource::Commit => git.get_hash(root_dir, &self.to_commit)?,
+            ToSource::Worktree | ToSource::Index => self.to_commit.clone(),
+        };
+        Ok((from, to))
     }
 
-    /// Copies the differential files between the commits specified in the constructor
-    pub fn copy<W: Write>(&self, w: &mut W) -> Result<()> {
+    /// Returns the filtered diff and its changed files
+    fn diff(&self, root_dir: &Path) -> Result<(GitDiff, Vec<FileChange>)> {
+        let pathspecs = pathspec::build(
+            root_dir,
+            &self.options.include_paths,
+            &self.options.exclude_paths,
+            &self.options.pathspecs,
+        )?;
+        let to_source = ToSource::of(&self.to_commit);
         let gitdiff = GitDiff::new(
             &self.git_path,
             &self.from_commit,
-            Some(&self.to_commit),
+            (to_source == ToSource::Commit).then_some(&self.to_commit),
             &self.target_dir,
-        )?;
-        let files = gitdiff.name_only()?;
-        if files.is_empty() {
-            writeln!(
-                w,
-                "There are no files with differences between {} and {}",
-                self.from_commit, self.to_commit
-            )?;
-            return Ok(());
+        )?
+        .with_staged(to_source == ToSource::Index)
+        .with_pathspecs(pathspecs);
+        let changes = gitdiff.name_status()?;
+        Ok((gitdiff, changes))
+    }
+
+    /// The files [`FilesCopy::copy`] writes for `manifest`, in the order they are written
+    fn planned_writes(&self, manifest: &Manifest, merged: &[String]) -> Vec<PathBuf> {
+        let output_dir = self.output_dir.as_path();
+        let archive = self.options.archive.map(|x| x.archive_path(output_dir));
+        if manifest.files.is_empty() {
+            return Vec::new();
+        }
+        if let (Some(archive), true) = (archive.clone(), self.options.archive_only) {
+            return vec![archive, self.report_file()];
         }
 
-        writeln!(
-            w,
-            "Updated files between {} and {}:",
-            self.from_commit, self.to_commit
-        )?;
-        for file in files.iter() {
-            writeln!(w, "\t{}", file)?;
+        let from_files = manifest
+            .files
+            .iter()
+            .filter_map(|x| x.path_at_from())
+            .collect::<Vec<_>>();
+        let to_files = manifest
+            .files
+            .iter()
+            .filter_map(|x| x.path_at_to())
+            .collect::<Vec<_>>();
+        let mut writes = Vec::new();
+        writes.extend(from_files.iter().map(|x| output_dir.join("from").join(x)));
+        writes.extend(to_files.iter().map(|x| output_dir.join("to").join(x)));
+        writes.push(output_dir.join(MANIFEST_FILE));
+        writes.push(output_dir.join(DELETED_FILE));
+        if self.options.merge_with.is_some() {
+            let merged_dir = output_dir.join(merge::MERGED_DIR);
+            writes.extend(merged.iter().map(|x| merged_dir.join(x)));
+            writes.push(output_dir.join(merge::MERGE_SUMMARY_FILE));
         }
+        for (enabled, name) in [
+            (self.options.scripts, "apply"),
+            (self.options.rollback, "rollback"),
+        ] {
+            if enabled {
+                writes.push(output_dir.join(format!("{name}.sh")));
+                writes.push(output_dir.join(format!("{name}.ps1")));
+            }
+        }
+        if self.options.patches {
+            let patches_dir = output_dir.join("patches");
+            writes.extend(
+                manifest
+                    .files
+                    .iter()
+                    .map(|x| patches_dir.join(format!("{}.patch", x.path))),
+            );
+            writes.push(output_dir.join("changes.patch"));
+        }
+        if self.options.checksums {
+            if !from_files.is_empty() {
+                writes.push(output_dir.join("from.sha256"));
+            }
+            if !to_files.is_empty() {
+                writes.push(output_dir.join("to.sha256"));
+            }
+            writes.push(output_dir.join(checksum::SHA256SUMS));
+        }
+        writes.extend(archive);
+        writes.push(self.report_file());
+        writes
+    }
 
-        // check output directory
-        fs::create_dir_all(&self.output_dir)?;
-        // Kept for API compatibility with the previous checkout/reset implementation.
-        let _ = &self.current_commit;
+    /// Extracts both commits into a temporary directory and returns the files the merge with
+    /// `target_dir` would write under `merged/`. The extraction streams blobs like
+    /// [`Extraction::CatFile`], which writes the same files as a worktree but creates none.
+    fn planned_merges(
+        &self,
+        root_dir: &Path,
+        changes: &[FileChange],
+        manifest: &Manifest,
+        target_dir: &Path,
+    ) -> Result<Vec<String>> {
+        let staging_dir = std::env::temp_dir()
+            .join("gde-staging")
+            .join(uuid::Uuid::new_v4().to_string());
+        let _staging_guard = Defer::new(|| {
+            let _ = fs::remove_dir_all(&staging_dir);
+        });
+        let from_files = changes
+            .iter()
+            .filter_map(|x| x.from_path().map(Into::into))
+            .collect::<Vec<String>>();
+        let to_files = changes
+            .iter()
+            .filter_map(|x| x.to_path().map(Into::into))
+            .collect::<Vec<String>>();
+        self.extract_with_cat_file(
+            ToSource::of(&self.to_commit),
+            root_dir,
+            (&from_files, &staging_dir.join("from")),
+            (&to_files, &staging_dir.join("to")),
+            &mut Discard,
+        )?;
+        merge::files_to_merge(&staging_dir, manifest, target_dir)
+    }
+
+    /// `report.json` in the output directory, or `<output dir>.report.json` next to the archive
+    /// when only the archive is written
+    fn report_file(&self) -> PathBuf {
+        if self.options.archive.is_some() && self.options.archive_only {
+            let mut path = self.output_dir.clone().into_os_string();
+            path.push(format!(".{REPORT_FILE}"));
+            PathBuf::from(path)
+        } else {
+            self.output_dir.join(REPORT_FILE)
+        }
+    }
 
+    /// Copies the files from detached worktrees of both commits
+    fn extract_with_worktrees<O: Observer + ?Sized>(
+        &self,
+        to_source: ToSource,
+        root_dir: &Path,
+        (from_files, from_dir): (&[String], &Path),
+        (to_files, to_dir): (&[String], &Path),
+        observer: &mut O,
+    ) -> Result<()> {
         let worktree_base_dir = std::env::temp_dir().join("gde-worktrees");
-        let worktree_session_dir = worktree_base_dir.join(uuid::Uuid::new_v4().to_string());
-        let from_worktree_dir = worktree_session_dir.join("from");
-        let to_worktree_dir = worktree_session_dir.join("to");
+        let session = uuid::Uuid::new_v4().to_string();
+        let worktree_session_dir = worktree_base_dir.join(&session);
+        // git names the worktree's admin directory after the last path component, so it has to be
+        // unique for runs on the same repository not to race
+        let from_worktree_dir = worktree_session_dir.join(format!("from-{session}"));
         let git_worktree = GitWorktree::new(&self.git_path, &self.target_dir)?;
         git_worktree.add_detached(&from_worktree_dir, &self.from_commit)?;
+        observer.notify(Event::WorktreeCreated {
+            rev: self.from_commit.clone(),
+            path: from_worktree_dir.clone(),
+        })?;
         let _from_guard = WorktreeGuard::new(
             &git_worktree,
             &from_worktree_dir,
             &worktree_session_dir,
             &worktree_base_dir,
         );
-        git_worktree.add_detached(&to_worktree_dir, &self.to_commit)?;
-        let _to_guard = WorktreeGuard::new(
-            &git_worktree,
-            &to_worktree_dir,
-            &worktree_session_dir,
-            &worktree_base_dir,
-        );
+        let to_worktree_dir = match to_source {
+            ToSource::Commit => worktree_session_dir.join(format!("to-{session}")),
+            ToSource::Worktree => root_dir.to_path_buf(),
+            ToSource::Index => worktree_session_dir.join("index"),
+        };
+        let _to_guard = match to_source {
+            ToSource::Commit => {
+                git_worktree.add_detached(&to_worktree_dir, &self.to_commit)?;
+                observer.notify(Event::WorktreeCreated {
+                    rev: self.to_commit.clone(),
+                    path: to_worktree_dir.clone(),
+                })?;
+                Some(WorktreeGuard::new(
+                    &git_worktree,
+                    &to_worktree_dir,
+                    &worktree_session_dir,
+                    &worktree_base_dir,
+                ))
+            }
+            ToSource::Worktree | ToSource::Index => None,
+        };
+        let _index_guard = (to_source == ToSource::Index).then(|| {
+            Defer::new(|| {
+                let _ = fs::remove_dir_all(&to_worktree_dir);
+            })
+        });
+        if to_source == ToSource::Index {
+            GitCheckoutIndex::new(&self.git_path, &self.target_dir)?
+                .checkout_to(&to_worktree_dir, to_files)?;
+        }
 
         // Copy files from "From Commit"
-        let from_dir = self.output_dir.join("from");
-        writeln!(w, "Copiying files from \"{}\"...", self.from_commit)?;
-        let from = FilesCopyInner::new(&files, &from_worktree_dir, &from_dir);
-        from.copy(w)?;
+        observer.notify(Event::Extracting {
+            rev: self.from_commit.clone(),
+        })?;
+        let from = FilesCopyInner::new(from_files, &from_worktree_dir, from_dir);
+        from.copy(observer)?;
 
         // Copy files from "To Commit"
-        let to_dir = self.output_dir.join("to");
-        writeln!(w, "Copiying files from \"{}\"...", self.to_commit)?;
-        let to = FilesCopyInner::new(&files, &to_worktree_dir, &to_dir);
-        to.copy(w)?;
+        observer.notify(Event::Extracting {
+            rev: self.to_commit.clone(),
+        })?;
+        let to = FilesCopyInner::new(to_files, &to_worktree_dir, to_dir);
+        to.copy(observer)
+    }
+
+    /// Reads only the changed blobs of both commits without creating worktrees
+    fn extract_with_cat_file<O: Observer + ?Sized>(
+        &self,
+        to_source: ToSource,
+        root_dir: &Path,
+        (from_files, from_dir): (&[String], &Path),
+        (to_files, to_dir): (&[String], &Path),
+        observer: &mut O,
+    ) -> Result<()> {
+        observer.notify(Event::Extracting {
+            rev: self.from_commit.clone(),
+        })?;
+        self.write_blobs(&self.from_commit, from_files, from_dir, observer)?;
+
+        observer.notify(Event::Extracting {
+            rev: self.to_commit.clone(),
+        })?;
+        match to_source {
+            ToSource::Commit => self.write_blobs(&self.to_commit, to_files, to_dir, observer)?,
+            // The index is read as ":<path>"
+            ToSource::Index => self.write_blobs("", to_files, to_dir, observer)?,
+            ToSource::Worktree => FilesCopyInner::new(to_files, root_dir, to_dir).copy(observer)?,
+        }
+        Ok(())
+    }
+
+    /// Writes the blobs of `files` at `rev` (the index when empty) under `dest_dir` as they are
+    /// copied from a worktree. Executables, symlinks and files that filters or eol conversion may
+    /// change are checked out by `git checkout-index` from a temporary index of `rev` and copied
+    /// from there, so a symlink is written as the file it points to. Git runs with an empty
+    /// working tree, so the attributes of `rev` apply rather than those of the working tree.
+    fn write_blobs<O: Observer + ?Sized>(
+        &self,
+        rev: &str,
+        files: &[String],
+        dest_dir: &Path,
+        observer: &mut O,
+    ) -> Result<()> {
+        let temp_dir = std::env::temp_dir().join(format!("gde-index-{}", uuid::Uuid::new_v4()));
+        let work_tree = temp_dir.join("worktree");
+        fs::create_dir_all(&work_tree)?;
+        let _temp_dir_guard = Defer::new(|| {
+            let _ = fs::remove_dir_all(&temp_dir);
+        });
+        let index_file = (!rev.is_empty()).then(|| temp_dir.join("index"));
+        if let Some(index_file) = index_file.as_ref() {
+            GitReadTree::new(&self.git_path, &self.target_dir)?.read_into(rev, index_file)?;
+        }
+
+        let modes = GitLsFiles::new(&self.git_path, &self.target_dir)?
+            .with_index_file(index_file.clone())
+            .modes()?;
+        let cat_file =
+            GitCatFile::new(&self.git_path, &self.target_dir)?.with_index_file(index_file.clone());
+        let filtered = cat_file.filtered_paths(files)?;
+        let (checkout, raw): (Vec<String>, Vec<String>) = files.iter().cloned().partition(|x| {
+            filtered.contains(x) || modes.get(x).is_some_and(|mode| mode != "100644")
+        });
+        cat_file.blobs(rev, &raw, |blob| {
+            let dest_file = dest_dir.join(&blob.path);
+            if let Some(dir) = dest_file.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&dest_file, &blob.contents)?;
+            observer.notify(Event::FileCopied {
+                source: CopySource::Blob {
+                    rev: rev.to_string(),
+                    path: blob.path,
+                },
+                dest: dest_file,
+            })
+        })?;
+
+        if checkout.is_empty() {
+            return Ok(());
+        }
+        let checkout_dir = temp_dir.join("checkout");
+        let checkout_index = GitCheckoutIndex::new(&self.git_path, &self.target_dir)?
+            .with_index_file(index_file)
+            .with_work_tree(Some(work_tree));
+        let mut checked_out = checkout.iter().cloned().collect::<HashSet<_>>();
+        let mut pending = checkout.clone();
+        while !pending.is_empty() {
+            checkout_index.checkout_to(&checkout_dir, &pending)?;
+            // The targets of symlinks are checked out too, until every link resolves
+            pending = pending
+                .iter()
+                .filter_map(|x| dangling_link_target(&checkout_dir, x))
+                .flat_map(|x| path_prefixes(&x))
+                .filter(|x| modes.contains_key(x) && checked_out.insert(x.clone()))
+                .collect();
+        }
+        for path in checkout {
+            // Paths that are not files at `rev`, such as submodules and dangling symlinks, are
+            // not written
+            let source_file = checkout_dir.join(&path);
+            if source_file.is_file() {
+                let dest_file = dest_dir.join(&path);
+                if let Some(dir) = dest_file.parent() {
+                    fs::create_dir_all(dir)?;
+                }
+                fs::copy(&source_file, &dest_file)?;
+                observer.notify(Event::FileCopied {
+                    source: CopySource::Blob {
+                        rev: rev.to_string(),
+                        path,
+                    },
+                    dest: dest_file,
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Packs `output_dir` with the files under `from/` stamped with the "from" commit time and
+    /// everything else with the "to" commit time (the current time for WORKTREE and INDEX)
+    fn write_archive(
+        &self,
+        format: ArchiveFormat,
+        output_dir: &Path,
+        archive_file: &Path,
+    ) -> Result<()> {
+        let revision = GitRevision::new(&self.git_path, &self.target_dir)?;
+        let from_timestamp = revision.commit_timestamp(&self.from_commit)?;
+        let to_timestamp = match ToSource::of(&self.to_commit) {
+            ToSource::Commit => revision.commit_timestamp(&self.to_commit)?,
+            ToSource::Worktree | ToSource::Index => std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)?
+                .as_secs() as i64,
+        };
+        if let Some(dir) = archive_file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        archive::write_archive(format, output_dir, archive_file, |path| {
+            if path.starts_with("from/") {
+                from_timestamp
+            } else {
+                to_timestamp
+            }
+        })
+    }
+
+    /// Writes one unified diff per changed file under `patches/` and a combined `changes.patch`.
+    /// Binary files are kept as git's "Binary files ... differ" marker instead of raw bytes.
+    fn write_patches<O: Observer + ?Sized>(
+        gitdiff: &GitDiff,
+        changes: &[FileChange],
+        output_dir: &Path,
+        observer: &mut O,
+    ) -> Result<()> {
+        let patches_dir = output_dir.join("patches");
+        for change in changes.iter() {
+            // Both paths are passed for renames so git can pair them up
+            let paths = [change.from_path(), change.to_path()]
+                .into_iter()
+                .flatten()
+                .dedup()
+                .collect::<Vec<_>>();
+            let file = paths[paths.len() - 1];
+            let dest_file = patches_dir.join(format!("{file}.patch"));
+            if let Some(dir) = dest_file.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(&dest_file, gitdiff.patch(&paths)?)?;
+            observer.notify(Event::PatchWritten {
+                binary: gitdiff.is_binary(file)?,
+                path: dest_file,
+            })?;
+        }
+
+        let dest_file = output_dir.join("changes.patch");
+        fs::write(&dest_file, gitdiff.patch(&[])?)?;
+        observer.notify(Event::PatchWritten {
+            path: dest_file,
+            binary: false,
+        })?;
         Ok(())
     }
 }
@@ -142,6 +809,37 @@ impl Drop for WorktreeGuard<'_> {
     }
 }
 
+/// The repository path the symlink `path` checked out under `root` points to, if the link does
+/// not resolve there. Links that leave the repository or are absolute are left as they are.
+fn dangling_link_target(root: &Path, path: &str) -> Option<String> {
+    let link = root.join(path);
+    if link.exists() {
+        return None;
+    }
+    let target = fs::read_link(&link).ok()?;
+    let mut parts = path.split('/').collect::<Vec<_>>();
+    parts.pop();
+    for component in target.components() {
+        match component {
+            Component::Normal(x) => parts.push(x.to_str()?),
+            Component::ParentDir => {
+                parts.pop()?;
+            }
+            Component::CurDir => (),
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/// `a`, `a/b` and `a/b/c` for `a/b/c`, as any of them can be a symlink
+fn path_prefixes(path: &str) -> Vec<String> {
+    path.match_indices('/')
+        .map(|(i, _)| path[..i].to_string())
+        .chain([path.to_string()])
+        .collect()
+}
+
 struct FilesCopyInner<'a> {
     /// The files to copy
     target_files: &'a [String],
@@ -162,7 +860,7 @@ impl<'a> FilesCopyInner<'a> {
         }
     }
 
-    fn copy<W: Write>(&self, w: &mut W) -> Result<()> {
+    fn copy<O: Observer + ?Sized>(&self, observer: &mut O) -> Result<()> {
         for file in self.target_files.iter() {
             let mut dir = PathBuf::from(file);
             dir.pop();
@@ -172,12 +870,10 @@ impl<'a> FilesCopyInner<'a> {
             if source_file.is_file() {
                 let dest_file = self.output_dir.join(file);
                 fs::copy(&source_file, &dest_file)?;
-                writeln!(
-                    w,
-                    "Copied: {} -> {}",
-                    source_file.display(),
-                    dest_file.display()
-                )?;
+                observer.notify(Event::FileCopied {
+                    source: CopySource::File(source_file),
+                    dest: dest_file,
+                })?;
             }
         }
 
@@ -185,14 +881,25 @@ impl<'a> FilesCopyInner<'a> {
     }
 }
 
+/// Drops the events of the extraction done by [`FilesCopy::plan`]
+struct Discard;
+
+impl Observer for Discard {
+    fn notify(&mut self, _event: Event) -> Result<()> {
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::testutil::git_test_lock;
+    use crate::event::TextObserver;
+    use crate::manifest::ChangeStatus;
     use flate2::read::GzDecoder;
     use outdir_tempdir::TempDir;
     use std::env;
     use std::fs::{self, File};
+    use std::io::Write;
     use std::process::Command;
     use tar::Archive;
 
@@ -406,7 +1113,6 @@ mod tests {
 
     #[test]
     fn test_copy() {
-        let _lock = git_test_lock();
         let dir = TempDir::new().autorm();
         let tempdir = dir.path();
         let f = File::open(get_test_file()).unwrap();
@@ -426,7 +1132,7 @@ mod tests {
         );
 
         let mut null = NullWriter;
-        f.copy(&mut null).unwrap();
+        f.copy(&mut TextObserver::new(&mut null)).unwrap();
 
         let from_dir = output_dir.join("from");
         let to_dir = output_dir.join("to");
@@ -462,13 +1168,12 @@ mod tests {
 
     #[test]
     fn copy_outputs_changed_file_versions_to_from_and_to() {
-        let _lock = git_test_lock();
         let repo = TestRepo::new();
         let _ = repo._keep_dir_alive();
         let mut out = Vec::new();
 
         repo.files_copy(&repo.commit_a, &repo.commit_b)
-            .copy(&mut out)
+            .copy(&mut TextObserver::new(&mut out))
             .unwrap();
 
         assert_file_bytes(
@@ -483,13 +1188,12 @@ mod tests {
 
     #[test]
     fn copy_outputs_added_file_only_to_to_directory() {
-        let _lock = git_test_lock();
         let repo = TestRepo::new();
         let _ = repo._keep_dir_alive();
         let mut out = Vec::new();
 
         repo.files_copy(&repo.commit_a, &repo.commit_b)
-            .copy(&mut out)
+            .copy(&mut TextObserver::new(&mut out))
             .unwrap();
 
         assert_not_exists(repo.output_file("from", "added.txt"));
@@ -501,13 +1205,12 @@ mod tests {
 
     #[test]
     fn copy_outputs_deleted_file_only_to_from_directory() {
-        let _lock = git_test_lock();
         let repo = TestRepo::new();
         let _ = repo._keep_dir_alive();
         let mut out = Vec::new();
 
         repo.files_copy(&repo.commit_a, &repo.commit_b)
-            .copy(&mut out)
+            .copy(&mut TextObserver::new(&mut out))
             .unwrap();
 
         assert_file_bytes(
@@ -519,13 +1222,12 @@ mod tests {
 
     #[test]
     fn copy_does_not_output_unchanged_files() {
-        let _lock = git_test_lock();
         let repo = TestRepo::new();
         let _ = repo._keep_dir_alive();
         let mut out = Vec::new();
 
         repo.files_copy(&repo.commit_a, &repo.commit_b)
-            .copy(&mut out)
+            .copy(&mut TextObserver::new(&mut out))
             .unwrap();
 
         assert_not_exists(repo.output_file("from", "unchanged.txt"));
@@ -534,13 +1236,12 @@ mod tests {
 
     #[test]
     fn copy_preserves_nested_directory_structure() {
-        let _lock = git_test_lock();
         let repo = TestRepo::new();
         let _ = repo._keep_dir_alive();
         let mut out = Vec::new();
 
         repo.files_copy(&repo.commit_a, &repo.commit_b)
-            .copy(&mut out)
+            .copy(&mut TextObserver::new(&mut out))
             .unwrap();
 
         assert_file_bytes(
@@ -555,13 +1256,12 @@ mod tests {
 
     #[test]
     fn copy_succeeds_with_empty_diff_and_reports_no_files() {
-        let _lock = git_test_lock();
         let repo = TestRepo::new();
         let _ = repo._keep_dir_alive();
         let mut out = Vec::new();
 
         repo.files_copy(&repo.commit_b, &repo.commit_b)
-            .copy(&mut out)
+            .copy(&mut TextObserver::new(&mut out))
             .unwrap();
 
         let output = String::from_utf8(out).unwrap();
@@ -573,7 +1273,6 @@ mod tests {
 
     #[test]
     fn copy_succeeds_when_working_tree_has_unstaged_changes() {
-        let _lock = git_test_lock();
         let repo = TestRepo::new();
         let _ = repo._keep_dir_alive();
         write_bytes(repo.repo_dir.join("changed.txt"), b"dirty working tree\n");
@@ -581,7 +1280,7 @@ mod tests {
 
         let mut out = Vec::new();
         repo.files_copy(&repo.commit_a, &repo.commit_b)
-            .copy(&mut out)
+            .copy(&mut TextObserver::new(&mut out))
             .unwrap();
 
         assert_eq!(before_head, repo.head());
@@ -606,7 +1305,6 @@ mod tests {
 
     #[test]
     fn copy_succeeds_when_working_tree_has_staged_changes() {
-        let _lock = git_test_lock();
         let repo = TestRepo::new();
         let _ = repo._keep_dir_alive();
         write_bytes(repo.repo_dir.join("changed.txt"), b"staged working tree\n");
@@ -615,7 +1313,7 @@ mod tests {
 
         let mut out = Vec::new();
         repo.files_copy(&repo.commit_a, &repo.commit_b)
-            .copy(&mut out)
+            .copy(&mut TextObserver::new(&mut out))
             .unwrap();
 
         assert_eq!(before_head, repo.head());
@@ -637,14 +1335,13 @@ mod tests {
 
     #[test]
     fn copy_keeps_repository_head_after_completion() {
-        let _lock = git_test_lock();
         let repo = TestRepo::new();
         let _ = repo._keep_dir_alive();
         let before = repo.head();
         let mut out = Vec::new();
 
         repo.files_copy(&repo.commit_a, &repo.commit_b)
-            .copy(&mut out)
+            .copy(&mut TextObserver::new(&mut out))
             .unwrap();
 
         let after = repo.head();
@@ -654,13 +1351,12 @@ mod tests {
 
     #[test]
     fn copy_leaves_working_tree_clean_after_completion() {
-        let _lock = git_test_lock();
         let repo = TestRepo::new();
         let _ = repo._keep_dir_alive();
         let mut out = Vec::new();
 
         repo.files_copy(&repo.commit_a, &repo.commit_b)
-            .copy(&mut out)
+            .copy(&mut TextObserver::new(&mut out))
             .unwrap();
 
         assert_eq!("", run_git(&repo.repo_dir, &["diff", "--name-only"]).trim());
@@ -673,13 +1369,12 @@ mod tests {
 
     #[test]
     fn copy_keeps_output_dir_after_worktree_cleanup() {
-        let _lock = git_test_lock();
         let repo = TestRepo::new();
         let _ = repo._keep_dir_alive();
         let mut out = Vec::new();
 
         repo.files_copy(&repo.commit_a, &repo.commit_b)
-            .copy(&mut out)
+            .copy(&mut TextObserver::new(&mut out))
             .unwrap();
 
         assert_exists(&repo.output_dir);
@@ -690,14 +1385,13 @@ mod tests {
 
     #[test]
     fn copy_keeps_existing_output_dir_contents() {
-        let _lock = git_test_lock();
         let repo = TestRepo::new();
         let _ = repo._keep_dir_alive();
         write_bytes(repo.output_dir.join("keep.txt"), b"keep me\n");
         let mut out = Vec::new();
 
         repo.files_copy(&repo.commit_a, &repo.commit_b)
-            .copy(&mut out)
+            .copy(&mut TextObserver::new(&mut out))
             .unwrap();
 
         assert_file_bytes(repo.output_dir.join("keep.txt"), b"keep me\n");
@@ -707,13 +1401,12 @@ mod tests {
 
     #[test]
     fn copy_preserves_checkout_crlf_bytes() {
-        let _lock = git_test_lock();
         let repo = TestRepo::new();
         let _ = repo._keep_dir_alive();
         let mut out = Vec::new();
 
         repo.files_copy(&repo.commit_a, &repo.commit_b)
-            .copy(&mut out)
+            .copy(&mut TextObserver::new(&mut out))
             .unwrap();
 
         assert_eq!(
@@ -736,13 +1429,12 @@ mod tests {
 
     #[test]
     fn copy_preserves_binary_file_bytes() {
-        let _lock = git_test_lock();
         let repo = TestRepo::new();
         let _ = repo._keep_dir_alive();
         let mut out = Vec::new();
 
         repo.files_copy(&repo.commit_a, &repo.commit_b)
-            .copy(&mut out)
+            .copy(&mut TextObserver::new(&mut out))
             .unwrap();
 
         assert_file_bytes(
@@ -757,14 +1449,13 @@ mod tests {
 
     #[test]
     fn copy_keeps_untracked_files_in_source_repository() {
-        let _lock = git_test_lock();
         let repo = TestRepo::new();
         let _ = repo._keep_dir_alive();
         write_bytes(repo.repo_dir.join("local-only.txt"), b"local only\n");
         let mut out = Vec::new();
 
         repo.files_copy(&repo.commit_a, &repo.commit_b)
-            .copy(&mut out)
+            .copy(&mut TextObserver::new(&mut out))
             .unwrap();
 
         assert_exists(repo.repo_dir.join("local-only.txt"));
@@ -774,14 +1465,13 @@ mod tests {
 
     #[test]
     fn copy_succeeds_with_output_dir_inside_repository() {
-        let _lock = git_test_lock();
         let repo = TestRepo::new();
         let _ = repo._keep_dir_alive();
         let output_dir = repo.repo_dir.join("artifacts");
         let mut out = Vec::new();
 
         repo.files_copy_with_output_dir(&repo.commit_a, &repo.commit_b, &output_dir)
-            .copy(&mut out)
+            .copy(&mut TextObserver::new(&mut out))
             .unwrap();
 
         assert_exists(output_dir.join("from").join("changed.txt"));
@@ -789,4 +1479,991 @@ mod tests {
         assert_not_exists(repo.repo_dir.join(".gde-worktrees"));
         assert_not_exists(output_dir.join(".gde-worktrees"));
     }
+
+    #[test]
+    fn copy_writes_patches_when_requested() {
+        let repo = TestRepo::new();
+        let _ = repo._keep_dir_alive();
+        let mut out = Vec::new();
+
+        repo.files_copy(&repo.commit_a, &repo.commit_b)
+            .with_options(CopyOptions {
+                patches: true,
+                ..Default::default()
+            })
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
+
+        let patch = String::from_utf8(read_bytes(
+            repo.output_dir.join("patches").join("changed.txt.patch"),
+        ))
+        .unwrap();
+        assert!(patch.contains("-before change"));
+        assert!(patch.contains("+after change"));
+        assert!(!patch.contains("nested"));
+
+        let patch = String::from_utf8(read_bytes(
+            repo.output_dir
+                .join("patches")
+                .join("nested")
+                .join("path")
+                .join("file.txt.patch"),
+        ))
+        .unwrap();
+        assert!(patch.contains("+nested b"));
+
+        let combined =
+            String::from_utf8(read_bytes(repo.output_dir.join("changes.patch"))).unwrap();
+        assert!(combined.contains("+after change"));
+        assert!(combined.contains("+nested b"));
+        assert!(combined.contains("+added in commit b"));
+    }
+
+    #[test]
+    fn copy_marks_binary_files_in_patches() {
+        let repo = TestRepo::new();
+        let _ = repo._keep_dir_alive();
+        let mut out = Vec::new();
+
+        repo.files_copy(&repo.commit_a, &repo.commit_b)
+            .with_options(CopyOptions {
+                patches: true,
+                ..Default::default()
+            })
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
+
+        let patch = read_bytes(repo.output_dir.join("patches").join("bin.dat.patch"));
+        let patch = String::from_utf8(patch).unwrap();
+        assert!(patch.contains("Binary files"));
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("bin.dat.patch (binary)"));
+    }
+
+    #[test]
+    fn copy_does_not_write_patches_by_default() {
+        let repo = TestRepo::new();
+        let _ = repo._keep_dir_alive();
+        let mut out = Vec::new();
+
+        repo.files_copy(&repo.commit_a, &repo.commit_b)
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
+
+        assert_not_exists(repo.output_dir.join("patches"));
+        assert_not_exists(repo.output_dir.join("changes.patch"));
+    }
+
+    #[test]
+    fn copy_writes_manifest_with_change_status() {
+        let repo = TestRepo::new();
+        let _ = repo._keep_dir_alive();
+        let mut out = Vec::new();
+
+        repo.files_copy(&repo.commit_a, &repo.commit_b)
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
+
+        let manifest = Manifest::read(&repo.output_dir).unwrap();
+        let status_of = |path: &str| {
+            manifest
+                .files
+                .iter()
+                .find(|x| x.path == path)
+                .map(|x| x.status)
+        };
+        assert_eq!(Some(ChangeStatus::Added), status_of("added.txt"));
+        assert_eq!(Some(ChangeStatus::Modified), status_of("changed.txt"));
+        assert_eq!(Some(ChangeStatus::Deleted), status_of("deleted.txt"));
+        assert_eq!(None, status_of("unchanged.txt"));
+        assert_file_bytes(repo.output_dir.join("deleted.txt"), b"deleted.txt\n");
+    }
+
+    #[test]
+    fn copy_reports_renamed_files_as_deleted_source() {
+        let repo = TestRepo::new();
+        let _ = repo._keep_dir_alive();
+        run_git(&repo.repo_dir, &["mv", "unchanged.txt", "renamed.txt"]);
+        let commit_d = commit_all(&repo.repo_dir, "commit d");
+        let mut out = Vec::new();
+
+        repo.files_copy(&repo.commit_c, &commit_d)
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
+
+        assert_file_bytes(repo.output_file("from", "unchanged.txt"), b"stable\n");
+        assert_file_bytes(repo.output_file("to", "renamed.txt"), b"stable\n");
+        assert_not_exists(repo.output_file("to", "unchanged.txt"));
+        assert_file_bytes(repo.output_dir.join("deleted.txt"), b"unchanged.txt\n");
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("renamed: unchanged.txt -> renamed.txt (100%)"));
+        let manifest = Manifest::read(&repo.output_dir).unwrap();
+        assert_eq!(ChangeStatus::Renamed, manifest.files[0].status);
+        assert_eq!(
+            Some("unchanged.txt"),
+            manifest.files[0].from_path.as_deref()
+        );
+    }
+
+    #[test]
+    fn copy_writes_reproducible_zip_archive() {
+        let repo = TestRepo::new();
+        let _ = repo._keep_dir_alive();
+        // Commits dated years ago, so entries stamped with the file system time would stand out
+        let commit_at = |date: &str| {
+            run_git(&repo.repo_dir, &["add", "-A"]);
+            let output = Command::new("git")
+                .args(["commit", "-m", date])
+                .env("GIT_COMMITTER_DATE", date)
+                .current_dir(&repo.repo_dir)
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "{output:?}");
+            repo.head()
+        };
+        write_bytes(repo.repo_dir.join("run.sh"), b"#!/bin/sh\necho a\n");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let run = repo.repo_dir.join("run.sh");
+            fs::set_permissions(&run, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        let commit_d = commit_at("2001-02-03T04:05:06Z");
+        write_bytes(repo.repo_dir.join("run.sh"), b"#!/bin/sh\necho b\n");
+        write_bytes(
+            repo.repo_dir.join("nested").join("path").join("file.txt"),
+            b"nested e\n",
+        );
+        write_bytes(repo.repo_dir.join("bin.dat"), &[0x00, 0xfe, 0x0d]);
+        fs::remove_file(repo.repo_dir.join("unchanged.txt")).unwrap();
+        let commit_e = commit_at("2002-03-04T05:06:08Z");
+
+        let options = CopyOptions {
+            scripts: true,
+            archive: Some(ArchiveFormat::Zip),
+            ..Default::default()
+        };
+        let first_dir = repo.output_dir.join("first");
+        let second_dir = repo.output_dir.join("second");
+        let mut out = Vec::new();
+
+        repo.files_copy_with_output_dir(&commit_d, &commit_e, &first_dir)
+            .with_options(options.clone())
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
+        repo.files_copy_with_output_dir(&commit_d, &commit_e, &second_dir)
+            .with_options(options)
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
+
+        let first = read_bytes(repo.output_dir.join("first.zip"));
+        assert_eq!(first, read_bytes(repo.output_dir.join("second.zip")));
+        assert_exists(first_dir.join("to").join("run.sh"));
+
+        let mut zip = zip::ZipArchive::new(std::io::Cursor::new(first)).unwrap();
+        let names = zip
+            .file_names()
+            .map(|x| x.unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert!(names.contains(&"from/unchanged.txt".to_string()));
+        assert!(names.contains(&"to/nested/path/file.txt".to_string()));
+        assert!(names.contains(&"manifest.json".to_string()));
+        let from_time = zip::DateTime::from_date_and_time(2001, 2, 3, 4, 5, 6).unwrap();
+        let to_time = zip::DateTime::from_date_and_time(2002, 3, 4, 5, 6, 8).unwrap();
+        for name in names.iter() {
+            let expected = if name.starts_with("from/") {
+                from_time
+            } else {
+                to_time
+            };
+            let entry = zip.by_name(name).unwrap();
+            assert_eq!(Some(expected), entry.last_modified(), "{name}");
+        }
+
+        let mode_of = |zip: &mut zip::ZipArchive<_>, name: &str| {
+            zip.by_name(name).unwrap().unix_mode().unwrap() & 0o777
+        };
+        assert_eq!(0o644, mode_of(&mut zip, "to/bin.dat"));
+        assert_eq!(0o644, mode_of(&mut zip, "apply.ps1"));
+        #[cfg(unix)]
+        {
+            assert_eq!(0o755, mode_of(&mut zip, "apply.sh"));
+            assert_eq!(0o755, mode_of(&mut zip, "from/run.sh"));
+            assert_eq!(0o755, mode_of(&mut zip, "to/run.sh"));
+        }
+        let mut bytes = Vec::new();
+        std::io::Read::read_to_end(&mut zip.by_name("to/bin.dat").unwrap(), &mut bytes).unwrap();
+        assert_eq!(bytes, repo.rev_file_bytes(&commit_e, "bin.dat"));
+    }
+
+    #[test]
+    fn copy_writes_only_tar_gz_archive_when_requested() {
+        let repo = TestRepo::new();
+        let _ = repo._keep_dir_alive();
+        let mut out = Vec::new();
+
+        repo.files_copy(&repo.commit_a, &repo.commit_b)
+            .with_options(CopyOptions {
+                rollback: true,
+                archive: Some(ArchiveFormat::TarGz),
+                archive_only: true,
+                ..Default::default()
+            })
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
+
+        assert_not_exists(&repo.output_dir);
+        let archive_file = ArchiveFormat::TarGz.archive_path(&repo.output_dir);
+        let from_time = run_git(
+            &repo.repo_dir,
+            &["show", "-s", "--format=%ct", &repo.commit_a],
+        );
+        let to_time = run_git(
+            &repo.repo_dir,
+            &["show", "-s", "--format=%ct", &repo.commit_b],
+        );
+        let mut archive = Archive::new(GzDecoder::new(File::open(archive_file).unwrap()));
+        let mut names = Vec::new();
+        for entry in archive.entries().unwrap() {
+            let entry = entry.unwrap();
+            let name = entry.path().unwrap().display().to_string();
+            let expected = if name.starts_with("from/") {
+                &from_time
+            } else {
+                &to_time
+            };
+            assert_eq!(expected.trim(), entry.header().mtime().unwrap().to_string());
+            let mode = entry.header().mode().unwrap();
+            if cfg!(unix) && name == "rollback.sh" {
+                assert_eq!(0o755, mode);
+            } else {
+                assert_eq!(0o644, mode, "{name}");
+            }
+            names.push(name);
+        }
+
+        let mut sorted = names.clone();
+        sorted.sort();
+        assert_eq!(sorted, names);
+        assert!(names.contains(&"from/changed.txt".to_string()));
+        assert!(names.contains(&"to/added.txt".to_string()));
+    }
+
+    #[test]
+    fn copy_merges_files_changed_in_target() {
+        let repo = TestRepo::new();
+        let _ = repo._keep_dir_alive();
+        write_bytes(repo.repo_dir.join("notes.txt"), b"1\n2\n3\n4\n5\n");
+        write_bytes(repo.repo_dir.join("changed.txt"), b"before change\n");
+        let commit_d = commit_all(&repo.repo_dir, "commit d");
+        write_bytes(repo.repo_dir.join("notes.txt"), b"one\n2\n3\n4\n5\n");
+        write_bytes(repo.repo_dir.join("changed.txt"), b"after change\n");
+        fs::remove_file(repo.repo_dir.join("unchanged.txt")).unwrap();
+        let commit_e = commit_all(&repo.repo_dir, "commit e");
+
+        let target_dir = repo.dir.path().join("customer");
+        export_tree(&repo.repo_dir, &commit_d, &target_dir);
+        write_bytes(target_dir.join("notes.txt"), b"1\n2\n3\n4\nfive\n");
+        write_bytes(target_dir.join("changed.txt"), b"customer change\n");
+        write_bytes(target_dir.join("unchanged.txt"), b"customer change\n");
+        let mut out = Vec::new();
+
+        repo.files_copy(&commit_d, &commit_e)
+            .with_options(CopyOptions {
+                merge_with: Some(target_dir),
+                ..Default::default()
+            })
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
+
+        let merged_dir = repo.output_dir.join(merge::MERGED_DIR);
+        assert_file_bytes(merged_dir.join("notes.txt"), b"one\n2\n3\n4\nfive\n");
+        let changed = String::from_utf8(read_bytes(merged_dir.join("changed.txt"))).unwrap();
+        assert!(changed.contains(&format!("<<<<<<< {commit_e}\nafter change\n")));
+        assert!(changed.contains("=======\ncustomer change\n>>>>>>> target\n"));
+        assert_not_exists(merged_dir.join("unchanged.txt"));
+        assert_file_bytes(
+            repo.output_dir.join(merge::MERGE_SUMMARY_FILE),
+            b"conflict: changed.txt (1 conflict)\n\
+              clean: notes.txt\n\
+              deleted: unchanged.txt (changed in the target)\n",
+        );
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("2 file(s) need manual resolution"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn copy_reports_type_changed_files_instead_of_merging() {
+        use std::os::unix::fs::symlink;
+
+        let repo = TestRepo::new();
+        let _ = repo._keep_dir_alive();
+        write_bytes(repo.repo_dir.join("becomes-link.txt"), b"file\n");
+        symlink("missing.txt", repo.repo_dir.join("becomes-file.txt")).unwrap();
+        let commit_d = commit_all(&repo.repo_dir, "commit d");
+        fs::remove_file(repo.repo_dir.join("becomes-link.txt")).unwrap();
+        symlink("missing.txt", repo.repo_dir.join("becomes-link.txt")).unwrap();
+        fs::remove_file(repo.repo_dir.join("becomes-file.txt")).unwrap();
+        write_bytes(repo.repo_dir.join("becomes-file.txt"), b"file\n");
+        let commit_e = commit_all(&repo.repo_dir, "commit e");
+
+        let target_dir = repo.dir.path().join("customer");
+        fs::create_dir_all(&target_dir).unwrap();
+        write_bytes(target_dir.join("becomes-link.txt"), b"customer change\n");
+        write_bytes(target_dir.join("becomes-file.txt"), b"customer change\n");
+        let mut out = Vec::new();
+
+        repo.files_copy(&commit_d, &commit_e)
+            .with_options(CopyOptions {
+                merge_with: Some(target_dir),
+                ..Default::default()
+            })
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
+
+        assert_not_exists(repo.output_dir.join(merge::MERGED_DIR));
+        assert_file_bytes(
+            repo.output_dir.join(merge::MERGE_SUMMARY_FILE),
+            b"not a file: becomes-file.txt (not merged)\n\
+              not a file: becomes-link.txt (not merged)\n",
+        );
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("2 file(s) need manual resolution"));
+    }
+
+    #[test]
+    fn copy_does_not_merge_unchanged_or_binary_files() {
+        let repo = TestRepo::new();
+        let _ = repo._keep_dir_alive();
+        let target_dir = repo.dir.path().join("customer");
+        export_tree(&repo.repo_dir, &repo.commit_a, &target_dir);
+        write_bytes(target_dir.join("bin.dat"), &[0x00, 0x42]);
+        write_bytes(target_dir.join("added.txt"), b"added by customer\n");
+        let mut out = Vec::new();
+
+        repo.files_copy(&repo.commit_a, &repo.commit_b)
+            .with_options(CopyOptions {
+                merge_with: Some(target_dir),
+                ..Default::default()
+            })
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
+
+        let merged_dir = repo.output_dir.join(merge::MERGED_DIR);
+        assert_not_exists(merged_dir.join("changed.txt"));
+        assert_not_exists(merged_dir.join("bin.dat"));
+        assert_exists(merged_dir.join("added.txt"));
+        assert_file_bytes(
+            repo.output_dir.join(merge::MERGE_SUMMARY_FILE),
+            b"conflict: added.txt (1 conflict)\nbinary: bin.dat (not merged)\n",
+        );
+    }
+
+    #[test]
+    fn copy_only_outputs_files_matching_path_filters() {
+        let repo = TestRepo::new();
+        let _ = repo._keep_dir_alive();
+        let mut out = Vec::new();
+
+        repo.files_copy(&repo.commit_a, &repo.commit_b)
+            .with_options(CopyOptions {
+                include_paths: vec!["*.txt".to_string(), "nested".to_string()],
+                exclude_paths: vec!["crlf*".to_string()],
+                patches: true,
+                ..Default::default()
+            })
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
+
+        assert_exists(repo.output_file("to", "changed.txt"));
+        assert_exists(repo.output_file("to", "added.txt"));
+        assert_exists(repo.output_file("to", "nested/path/file.txt"));
+        assert_not_exists(repo.output_file("to", "crlf.txt"));
+        assert_not_exists(repo.output_file("to", "bin.dat"));
+        let manifest = Manifest::read(&repo.output_dir).unwrap();
+        assert_eq!(4, manifest.files.len());
+        let patch = fs::read_to_string(repo.output_dir.join("changes.patch")).unwrap();
+        assert!(patch.contains("+after change"));
+        assert!(!patch.contains("crlf.txt"));
+    }
+
+    #[test]
+    fn copy_skips_files_in_ignore_file_and_pathspecs() {
+        let repo = TestRepo::new();
+        let _ = repo._keep_dir_alive();
+        write_bytes(
+            repo.repo_dir.join(pathspec::IGNORE_FILE),
+            b"# not shipped\nnested/\nbin.dat\n",
+        );
+        let mut out = Vec::new();
+
+        repo.files_copy(&repo.commit_a, &repo.commit_b)
+            .with_options(CopyOptions {
+                pathspecs: vec![":(exclude)added.txt".to_string()],
+                ..Default::default()
+            })
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
+
+        assert_exists(repo.output_file("to", "changed.txt"));
+        assert_exists(repo.output_file("to", "crlf.txt"));
+        assert_not_exists(repo.output_file("to", "nested/path/file.txt"));
+        assert_not_exists(repo.output_file("to", "bin.dat"));
+        assert_not_exists(repo.output_file("to", "added.txt"));
+    }
+
+    #[test]
+    fn copy_outputs_working_tree_as_to_side() {
+        let repo = TestRepo::new();
+        let _ = repo._keep_dir_alive();
+        write_bytes(repo.repo_dir.join("changed.txt"), b"hotfix\n");
+        fs::remove_file(repo.repo_dir.join("unchanged.txt")).unwrap();
+        write_bytes(repo.repo_dir.join("staged.txt"), b"staged\n");
+        run_git(&repo.repo_dir, &["add", "staged.txt"]);
+        write_bytes(repo.repo_dir.join("untracked.txt"), b"untracked\n");
+        let mut out = Vec::new();
+
+        repo.files_copy(&repo.commit_c, WORKTREE)
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
+
+        assert_file_bytes(repo.output_file("from", "changed.txt"), b"after change\n");
+        assert_file_bytes(repo.output_file("to", "changed.txt"), b"hotfix\n");
+        assert_file_bytes(repo.output_file("to", "staged.txt"), b"staged\n");
+        assert_file_bytes(repo.output_file("from", "unchanged.txt"), b"stable\n");
+        assert_not_exists(repo.output_file("to", "unchanged.txt"));
+        assert_not_exists(repo.output_file("to", "untracked.txt"));
+        assert_file_bytes(repo.repo_dir.join("changed.txt"), b"hotfix\n");
+        let manifest = Manifest::read(&repo.output_dir).unwrap();
+        assert_eq!(WORKTREE, manifest.to);
+    }
+
+    #[test]
+    fn copy_outputs_staged_contents_as_to_side() {
+        let repo = TestRepo::new();
+        let _ = repo._keep_dir_alive();
+        write_bytes(repo.repo_dir.join("changed.txt"), b"staged fix\n");
+        write_bytes(
+            repo.repo_dir.join("nested/path/file.txt"),
+            b"staged nested\n",
+        );
+        run_git(
+            &repo.repo_dir,
+            &["add", "changed.txt", "nested/path/file.txt"],
+        );
+        write_bytes(repo.repo_dir.join("changed.txt"), b"not staged yet\n");
+        write_bytes(repo.repo_dir.join("unchanged.txt"), b"not staged\n");
+        let mut out = Vec::new();
+
+        repo.files_copy(&repo.commit_c, INDEX)
+            .with_options(CopyOptions {
+                patches: true,
+                ..Default::default()
+            })
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
+
+        assert_file_bytes(repo.output_file("to", "changed.txt"), b"staged fix\n");
+        assert_file_bytes(
+            repo.output_file("to", "nested/path/file.txt"),
+            b"staged nested\n",
+        );
+        assert_not_exists(repo.output_file("to", "unchanged.txt"));
+        let patch = fs::read_to_string(repo.output_dir.join("changes.patch")).unwrap();
+        assert!(patch.contains("+staged fix"));
+        assert!(!patch.contains("not staged"));
+        assert_file_bytes(repo.repo_dir.join("changed.txt"), b"not staged yet\n");
+    }
+
+    /// Copies `from`..`to` with both extractions and checks they write the same files, bytes and
+    /// modes. Returns the listed files and the cat-file output directory.
+    fn compare_extractions(repo: &TestRepo, from: &str, to: &str) -> (Vec<String>, PathBuf) {
+        let name = &to[..7];
+        let worktree_dir = repo.output_dir.join(format!("worktree-{name}"));
+        let cat_file_dir = repo.output_dir.join(format!("cat-file-{name}"));
+        let mut out = Vec::new();
+
+        repo.files_copy_with_output_dir(from, to, &worktree_dir)
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
+        repo.files_copy_with_output_dir(from, to, &cat_file_dir)
+            .with_options(CopyOptions {
+                extraction: Extraction::CatFile,
+                ..Default::default()
+            })
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
+
+        let files = crate::list_files(&worktree_dir).unwrap();
+        assert_eq!(files, crate::list_files(&cat_file_dir).unwrap());
+        // The report records timings and the output directory
+        for file in files.iter().filter(|x| *x != REPORT_FILE) {
+            assert_file_bytes(
+                cat_file_dir.join(file),
+                &read_bytes(worktree_dir.join(file)),
+            );
+            assert_eq!(
+                fs::symlink_metadata(worktree_dir.join(file))
+                    .unwrap()
+                    .permissions(),
+                fs::symlink_metadata(cat_file_dir.join(file))
+                    .unwrap()
+                    .permissions(),
+                "{file}"
+            );
+        }
+        (files, cat_file_dir)
+    }
+
+    #[test]
+    fn copy_with_cat_file_matches_worktree_output() {
+        let repo = TestRepo::new();
+        let (files, cat_file_dir) = compare_extractions(&repo, &repo.commit_a, &repo.commit_b);
+        assert!(files.contains(&"to/crlf-normalized.txt".to_string()));
+        assert_file_bytes(
+            cat_file_dir.join("to").join("crlf-normalized.txt"),
+            b"line1\r\nline2 changed\r\n",
+        );
+        assert_file_bytes(
+            cat_file_dir.join("to").join("bin.dat"),
+            &repo.rev_file_bytes(&repo.commit_b, "bin.dat"),
+        );
+
+        // Executables keep their mode and symlinks are written as the file they point to
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{symlink, PermissionsExt};
+            write_bytes(repo.repo_dir.join("other.txt"), b"other\n");
+            let head = commit_all(&repo.repo_dir, "add a link target");
+            let run = repo.repo_dir.join("run.sh");
+            write_bytes(&run, b"#!/bin/sh\n");
+            fs::set_permissions(&run, fs::Permissions::from_mode(0o755)).unwrap();
+            symlink("other.txt", repo.repo_dir.join("link")).unwrap();
+            fs::create_dir_all(repo.repo_dir.join("nested")).unwrap();
+            symlink("../link", repo.repo_dir.join("nested").join("link")).unwrap();
+            symlink("missing", repo.repo_dir.join("dangling")).unwrap();
+            let linked = commit_all(&repo.repo_dir, "add an executable and symlinks");
+
+            let (files, cat_file_dir) = compare_extractions(&repo, &head, &linked);
+            // The link target is not a changed file
+            assert!(!files.contains(&"to/other.txt".to_string()), "{files:?}");
+            assert!(files.contains(&"to/nested/link".to_string()), "{files:?}");
+            assert!(!files.contains(&"to/dangling".to_string()), "{files:?}");
+            assert_file_bytes(
+                cat_file_dir.join("to").join("nested").join("link"),
+                b"other\n",
+            );
+            let mode = fs::metadata(cat_file_dir.join("to").join("run.sh"))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(0o755, mode & 0o777);
+        }
+    }
+
+    #[test]
+    fn copy_with_cat_file_uses_the_attributes_of_each_commit() {
+        let repo = TestRepo::new();
+        // Only the working tree loses the eol rule
+        write_bytes(repo.repo_dir.join(".gitattributes"), b"");
+        let mut out = Vec::new();
+
+        repo.files_copy(&repo.commit_a, &repo.commit_b)
+            .with_options(CopyOptions {
+                extraction: Extraction::CatFile,
+                ..Default::default()
+            })
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
+
+        assert_file_bytes(
+            repo.output_file("to", "crlf-normalized.txt"),
+            b"line1\r\nline2 changed\r\n",
+        );
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("crlf-normalized.txt"));
+    }
+
+    #[test]
+    fn copy_with_cat_file_reads_staged_contents() {
+        let repo = TestRepo::new();
+        let _ = repo._keep_dir_alive();
+        write_bytes(repo.repo_dir.join("changed.txt"), b"staged fix\n");
+        run_git(&repo.repo_dir, &["add", "changed.txt"]);
+        write_bytes(repo.repo_dir.join("changed.txt"), b"not staged yet\n");
+        let mut out = Vec::new();
+
+        repo.files_copy(&repo.commit_c, INDEX)
+            .with_options(CopyOptions {
+                extraction: Extraction::CatFile,
+                ..Default::default()
+            })
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
+
+        assert_file_bytes(repo.output_file("from", "changed.txt"), b"after change\n");
+        assert_file_bytes(repo.output_file("to", "changed.txt"), b"staged fix\n");
+    }
+
+    #[test]
+    fn copy_runs_concurrently_without_changing_current_dir() {
+        let repos = [TestRepo::new(), TestRepo::new()];
+        let current_dir = env::current_dir().unwrap();
+
+        std::thread::scope(|scope| {
+            for repo in repos.iter() {
+                scope.spawn(move || {
+                    for _ in 0..3 {
+                        repo.files_copy(&repo.commit_a, &repo.commit_b)
+                            .copy(&mut TextObserver::new(NullWriter))
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        assert_eq!(current_dir, env::current_dir().unwrap());
+        for repo in repos.iter() {
+            assert_file_bytes(repo.output_file("to", "changed.txt"), b"after change\n");
+            assert_file_bytes(repo.output_file("from", "deleted.txt"), b"delete me\n");
+        }
+    }
+
+    fn export_tree(repo_dir: &Path, rev: &str, dest: &Path) {
+        let output = Command::new("git")
+            .args(["archive", "--format=tar", rev])
+            .current_dir(repo_dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        Archive::new(output.stdout.as_slice()).unpack(dest).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn copy_writes_rollback_script_that_restores_from_tree() {
+        let repo = TestRepo::new();
+        let _ = repo._keep_dir_alive();
+        let target_dir = repo.dir.path().join("customer");
+        export_tree(&repo.repo_dir, &repo.commit_a, &target_dir);
+        let mut out = Vec::new();
+
+        repo.files_copy(&repo.commit_a, &repo.commit_b)
+            .with_options(CopyOptions {
+                scripts: true,
+                rollback: true,
+                ..Default::default()
+            })
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
+
+        assert_exists(repo.output_dir.join("rollback.ps1"));
+        let run = |script: &str| {
+            let output = Command::new("sh")
+                .arg(repo.output_dir.join(script))
+                .arg(&target_dir)
+                .output()
+                .unwrap();
+            assert!(
+                output.status.success(),
+                "{}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        };
+        run("apply.sh");
+        run("rollback.sh");
+
+        for path in [
+            "changed.txt",
+            "deleted.txt",
+            "nested/path/file.txt",
+            "bin.dat",
+        ] {
+            assert_file_bytes(
+                target_dir.join(path),
+                &read_bytes(repo.output_file("from", path)),
+            );
+        }
+        assert_not_exists(target_dir.join("added.txt"));
+        assert_file_bytes(target_dir.join("unchanged.txt"), b"stable\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn copy_writes_apply_script_that_turns_from_tree_into_to_tree() {
+        let repo = TestRepo::new();
+        let _ = repo._keep_dir_alive();
+        let target_dir = repo.dir.path().join("customer");
+        export_tree(&repo.repo_dir, &repo.commit_a, &target_dir);
+        let mut out = Vec::new();
+
+        repo.files_copy(&repo.commit_a, &repo.commit_b)
+            .with_options(CopyOptions {
+                scripts: true,
+                ..Default::default()
+            })
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
+
+        assert_exists(repo.output_dir.join("apply.ps1"));
+        let apply = |target: &Path| {
+            Command::new("sh")
+                .arg(repo.output_dir.join("apply.sh"))
+                .arg(target)
+                .output()
+                .unwrap()
+        };
+        let output = apply(&target_dir);
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        for path in [
+            "changed.txt",
+            "added.txt",
+            "nested/path/file.txt",
+            "bin.dat",
+        ] {
+            assert_file_bytes(
+                target_dir.join(path),
+                &read_bytes(repo.output_file("to", path)),
+            );
+        }
+        assert_not_exists(target_dir.join("deleted.txt"));
+        assert_file_bytes(target_dir.join("unchanged.txt"), b"stable\n");
+
+        // The target is no longer in the "from" state, so a second run must not touch it
+        write_bytes(target_dir.join("deleted.txt"), b"restored\n");
+        let output = apply(&target_dir);
+        assert!(!output.status.success());
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains("Modified: changed.txt"));
+        assert!(stderr.contains("Already exists: added.txt"));
+        assert_file_bytes(target_dir.join("deleted.txt"), b"restored\n");
+    }
+
+    #[test]
+    fn copy_writes_checksums_that_verify() {
+        let repo = TestRepo::new();
+        let _ = repo._keep_dir_alive();
+        let mut out = Vec::new();
+
+        repo.files_copy(&repo.commit_a, &repo.commit_b)
+            .with_options(CopyOptions {
+                checksums: true,
+                patches: true,
+                ..Default::default()
+            })
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
+
+        let from = fs::read_to_string(repo.output_dir.join("from.sha256")).unwrap();
+        assert!(from.contains(&format!(
+            "{}  deleted.txt\n",
+            checksum::sha256_file(repo.output_file("from", "deleted.txt")).unwrap()
+        )));
+        let sums = fs::read_to_string(repo.output_dir.join(checksum::SHA256SUMS)).unwrap();
+        assert!(sums.contains("  manifest.json\n"));
+        assert!(sums.contains("  patches/changed.txt.patch\n"));
+        assert!(checksum::verify(&repo.output_dir).unwrap().is_ok());
+
+        write_bytes(repo.output_file("to", "changed.txt"), b"tampered\n");
+        let report = checksum::verify(&repo.output_dir).unwrap();
+        assert_eq!(vec!["to/changed.txt"], report.mismatched);
+    }
+
+    #[test]
+    fn plan_lists_exactly_the_files_copy_writes() {
+        let repo = TestRepo::new();
+        let _ = repo._keep_dir_alive();
+        let target_dir = repo.dir.path().join("customer");
+        export_tree(&repo.repo_dir, &repo.commit_a, &target_dir);
+        write_bytes(target_dir.join("changed.txt"), b"customer change\n");
+        write_bytes(target_dir.join("added.txt"), b"customer addition\n");
+        write_bytes(target_dir.join("deleted.txt"), b"customer change\n");
+        write_bytes(target_dir.join("bin.dat"), &[0x00, 0x0a]);
+        let options = CopyOptions {
+            patches: true,
+            scripts: true,
+            rollback: true,
+            checksums: true,
+            merge_with: Some(target_dir),
+            ..Default::default()
+        };
+        let copy = repo
+            .files_copy(&format!("{}~1", repo.commit_b), &repo.commit_b)
+            .with_options(options);
+
+        let plan = copy.plan().unwrap();
+        assert_eq!(repo.commit_a, plan.from);
+        assert_eq!(repo.commit_b, plan.to);
+        assert_eq!(7, plan.files.len());
+        assert!(!repo.output_dir.exists());
+
+        copy.copy(&mut TextObserver::new(NullWriter)).unwrap();
+        let mut planned = plan
+            .writes
+            .iter()
+            .map(|x| {
+                x.strip_prefix(&repo.output_dir)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect::<Vec<_>>();
+        planned.sort();
+        assert_eq!(crate::list_files(&repo.output_dir).unwrap(), planned);
+        let merged = planned
+            .iter()
+            .filter(|x| x.starts_with("merged/"))
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["merged/added.txt", "merged/changed.txt"], merged);
+    }
+
+    #[test]
+    fn copy_writes_report_with_hashes_sizes_and_timings() {
+        let repo = TestRepo::new();
+        let _ = repo._keep_dir_alive();
+        let mut out = Vec::new();
+
+        let report = repo
+            .files_copy(&format!("{}~1", repo.commit_b), &repo.commit_b)
+            .with_options(CopyOptions {
+                checksums: true,
+                ..Default::default()
+            })
+            .copy(&mut TextObserver::new(&mut out))
+            .unwrap();
+
+        assert_eq!(repo.commit_a, report.from);
+        assert_eq!(repo.commit_b, report.to);
+        assert!(!report.git_version.is_empty());
+        let steps = report
+            .timings
+            .iter()
+            .map(|x| x.step.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec!["diff", "extract", "manifest", "checksums", "report"],
+            steps
+        );
+
+        let changed = report
+            .files
+            .iter()
+            .find(|x| x.entry.path == "changed.txt")
+            .unwrap();
+        let to = changed.to.as_ref().unwrap();
+        assert_eq!(b"after change\n".len() as u64, to.size);
+        assert_eq!(
+            checksum::sha256_file(repo.output_file("to", "changed.txt")).unwrap(),
+            to.sha256
+        );
+        let deleted = report
+            .files
+            .iter()
+            .find(|x| x.entry.path == "deleted.txt")
+            .unwrap();
+        assert!(deleted.from.is_some());
+        assert!(deleted.to.is_none());
+
+        let written: CopyReport =
+            serde_json::from_slice(&read_bytes(repo.output_dir.join(REPORT_FILE))).unwrap();
+        assert_eq!(report, written);
+        // The report is written after the checksums and does not break verification
+        assert!(checksum::verify(&repo.output_dir).unwrap().is_ok());
+    }
+
+    #[test]
+    fn copy_notifies_observer_of_progress() {
+        struct Events(Vec<Event>);
+        impl Observer for Events {
+            fn notify(&mut self, event: Event) -> Result<()> {
+                self.0.push(event);
+                Ok(())
+            }
+        }
+        let repo = TestRepo::new();
+        let _ = repo._keep_dir_alive();
+        let mut events = Events(Vec::new());
+
+        repo.files_copy(&repo.commit_a, &repo.commit_b)
+            .copy(&mut events)
+            .unwrap();
+
+        let events = events.0;
+        assert!(matches!(&events[0], Event::Started { changes, .. } if changes.len() == 7));
+        let worktrees = events
+            .iter()
+            .filter(|x| matches!(x, Event::WorktreeCreated { .. }))
+            .count();
+        assert_eq!(2, worktrees);
+        assert!(events.iter().any(|x| matches!(
+            x,
+            Event::FileCopied { dest, .. } if *dest == repo.output_file("to", "added.txt")
+        )));
+        assert!(events.contains(&Event::FileWritten {
+            path: repo.output_dir.join(REPORT_FILE)
+        }));
+        assert!(matches!(events.last(), Some(Event::Finished { .. })));
+    }
+
+    #[test]
+    fn copy_returns_typed_errors() {
+        let repo = TestRepo::new();
+        let dir = repo._keep_dir_alive();
+
+        let e = repo
+            .files_copy("no-such-commit", &repo.commit_b)
+            .copy(&mut TextObserver::new(NullWriter))
+            .unwrap_err();
+        let crate::Error::Git(crate::git::Error::UnknownRevision { rev, failure }) = e else {
+            panic!("{e}");
+        };
+        assert_eq!("no-such-commit", rev);
+        assert_eq!("git rev-parse no-such-commit", failure.command);
+        assert_eq!(Some(128), failure.status);
+
+        // The test directories are inside this crate's repository
+        let not_a_repo = std::env::temp_dir().join(format!("gde-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&not_a_repo).unwrap();
+        let _not_a_repo_guard = Defer::new(|| {
+            let _ = fs::remove_dir_all(&not_a_repo);
+        });
+        let e = FilesCopy::new("git", "HEAD", "HEAD", &not_a_repo, &repo.output_dir, "")
+            .plan()
+            .unwrap_err();
+        assert!(
+            matches!(e.git(), Some(crate::git::Error::NotARepository { .. })),
+            "{e}"
+        );
+
+        let e = FilesCopy::new(
+            dir.path().join("no-git"),
+            &repo.commit_a,
+            &repo.commit_b,
+            &repo.repo_dir,
+            &repo.output_dir,
+            "",
+        )
+        .plan()
+        .unwrap_err();
+        assert!(
+            matches!(e.git(), Some(crate::git::Error::GitNotFound { .. })),
+            "{e}"
+        );
+
+        let file = dir.path().join("file");
+        write_bytes(&file, b"not a directory\n");
+        let e = repo
+            .files_copy_with_output_dir(&repo.commit_a, &repo.commit_b, file.join("out"))
+            .copy(&mut TextObserver::new(NullWriter))
+            .unwrap_err();
+        assert!(
+            matches!(&e, crate::Error::OutputDirNotWritable { path, .. } if *path == file.join("out")),
+            "{e}"
+        );
+    }
 }
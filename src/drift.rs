@@ -0,0 +1,184 @@
+use crate::manifest::Manifest;
+use crate::script::ScriptPlan;
+use anyhow::{anyhow, Result};
+use std::fs;
+use std::path::Path;
+
+/// How a target directory differs from the `from/` snapshot of an output directory
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DriftReport {
+    /// Files in `from/` whose contents differ in the target
+    pub modified: Vec<String>,
+
+    /// Files in `from/` that do not exist in the target
+    pub missing: Vec<String>,
+
+    /// Files the package adds that already exist in the target
+    pub existing: Vec<String>,
+}
+
+impl DriftReport {
+    pub fn is_ok(&self) -> bool {
+        self.modified.is_empty() && self.missing.is_empty() && self.existing.is_empty()
+    }
+}
+
+/// Compares every file in `<output_dir>/from` with the same path in `target_dir`.
+/// With `ignore_eol`, CRLF and LF line endings are treated as equal.
+/// If the output directory has a manifest, files the package adds must not exist in the target yet.
+/// Fails if `output_dir` has neither `from/` nor a manifest, since it is then not a gde package.
+pub fn check_target(
+    output_dir: impl AsRef<Path>,
+    target_dir: impl AsRef<Path>,
+    ignore_eol: bool,
+) -> Result<DriftReport> {
+    let output_dir = output_dir.as_ref();
+    let target_dir = target_dir.as_ref();
+    if !target_dir.is_dir() {
+        return Err(anyhow!("{} is not a directory", target_dir.display()));
+    }
+
+    let from_dir = output_dir.join("from");
+    let manifest = output_dir.join(crate::manifest::MANIFEST_FILE);
+    if !from_dir.is_dir() && !manifest.is_file() {
+        return Err(anyhow!(
+            "{} is not a gde output directory (it has neither from/ nor {})",
+            output_dir.display(),
+            crate::manifest::MANIFEST_FILE
+        ));
+    }
+
+    let mut report = DriftReport::default();
+    if from_dir.is_dir() {
+        for path in crate::list_files(&from_dir)? {
+            let actual = target_dir.join(&path);
+            if !actual.is_file() {
+                report.missing.push(path);
+            } else if !same_contents(
+                &fs::read(from_dir.join(&path))?,
+                &fs::read(&actual)?,
+                ignore_eol,
+            ) {
+                report.modified.push(path);
+            }
+        }
+    }
+
+    if manifest.is_file() {
+        let plan = ScriptPlan::apply(&Manifest::read(output_dir)?);
+        report.existing = plan
+            .absent
+            .into_iter()
+            .filter(|x| target_dir.join(x).exists())
+            .collect();
+    }
+
+    Ok(report)
+}
+
+fn same_contents(expected: &[u8], actual: &[u8], ignore_eol: bool) -> bool {
+    if ignore_eol {
+        normalize_eol(expected) == normalize_eol(actual)
+    } else {
+        expected == actual
+    }
+}
+
+/// Replaces every CRLF with LF
+fn normalize_eol(bytes: &[u8]) -> Vec<u8> {
+    let mut ret = Vec::with_capacity(bytes.len());
+    let mut iter = bytes.iter().peekable();
+    while let Some(&b) = iter.next() {
+        if b == b'\r' && iter.peek() == Some(&&b'\n') {
+            continue;
+        }
+        ret.push(b);
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::FileChange;
+    use outdir_tempdir::TempDir;
+
+    fn write(path: impl AsRef<Path>, bytes: &[u8]) {
+        let path = path.as_ref();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, bytes).unwrap();
+    }
+
+    fn package() -> TempDir {
+        let dir = TempDir::new().autorm();
+        write(dir.path().join("from").join("a.txt"), b"a\nb\n");
+        write(dir.path().join("from").join("nested").join("c.txt"), b"c\n");
+        write(dir.path().join("to").join("a.txt"), b"a\n");
+        write(dir.path().join("to").join("added.txt"), b"added\n");
+        Manifest::new(
+            "a",
+            "b",
+            &[
+                FileChange::Modified("a.txt".to_string()),
+                FileChange::Deleted("nested/c.txt".to_string()),
+                FileChange::Added("added.txt".to_string()),
+            ],
+        )
+        .write(dir.path())
+        .unwrap();
+        dir
+    }
+
+    #[test]
+    fn check_target_accepts_matching_tree() {
+        let package = package();
+        let target = TempDir::new().autorm();
+        write(target.path().join("a.txt"), b"a\nb\n");
+        write(target.path().join("nested").join("c.txt"), b"c\n");
+        write(target.path().join("unrelated.txt"), b"unrelated\n");
+
+        let report = check_target(package.path(), target.path(), false).unwrap();
+        assert!(report.is_ok());
+    }
+
+    #[test]
+    fn check_target_rejects_a_directory_that_is_not_a_package() {
+        let not_package = TempDir::new().autorm();
+        write(not_package.path().join("a.txt"), b"a\n");
+        let target = TempDir::new().autorm();
+
+        let e = check_target(not_package.path(), target.path(), false).unwrap_err();
+        assert!(
+            e.to_string().contains("is not a gde output directory"),
+            "{e}"
+        );
+        assert!(check_target(not_package.path().join("missing"), target.path(), false).is_err());
+    }
+
+    #[test]
+    fn check_target_reports_drifted_files() {
+        let package = package();
+        let target = TempDir::new().autorm();
+        write(target.path().join("a.txt"), b"a\nlocal edit\n");
+        write(target.path().join("added.txt"), b"already here\n");
+
+        let report = check_target(package.path(), target.path(), false).unwrap();
+        assert!(!report.is_ok());
+        assert_eq!(vec!["a.txt"], report.modified);
+        assert_eq!(vec!["nested/c.txt"], report.missing);
+        assert_eq!(vec!["added.txt"], report.existing);
+    }
+
+    #[test]
+    fn check_target_can_ignore_line_endings() {
+        let package = package();
+        let target = TempDir::new().autorm();
+        write(target.path().join("a.txt"), b"a\r\nb\r\n");
+        write(target.path().join("nested").join("c.txt"), b"c\n");
+
+        let report = check_target(package.path(), target.path(), false).unwrap();
+        assert_eq!(vec!["a.txt"], report.modified);
+        let report = check_target(package.path(), target.path(), true).unwrap();
+        assert!(report.is_ok());
+    }
+}
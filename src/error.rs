@@ -0,0 +1,114 @@
+use crate::git;
+use std::io;
+use std::path::PathBuf;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Error of [`crate::FilesCopy`] and [`crate::AutoCopy`]
+#[derive(Debug)]
+pub enum Error {
+    /// A git command failed
+    Git(git::Error),
+
+    /// The output directory could not be created
+    OutputDirNotWritable { path: PathBuf, source: io::Error },
+
+    /// Any other failure, such as a file that could not be written
+    Other(anyhow::Error),
+}
+
+impl Error {
+    /// The git error behind this error, if there is one
+    pub fn git(&self) -> Option<&git::Error> {
+        match self {
+            Self::Git(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<git::Error> for Error {
+    fn from(value: git::Error) -> Self {
+        Self::Git(value)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Self::Other(value.into())
+    }
+}
+
+/// Keeps typed errors raised by helpers that return `anyhow::Result` typed
+impl From<anyhow::Error> for Error {
+    fn from(value: anyhow::Error) -> Self {
+        match value.downcast::<git::Error>() {
+            Ok(e) => Self::Git(e),
+            Err(value) => match value.downcast::<Self>() {
+                Ok(e) => e,
+                Err(value) => Self::Other(value),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Git(e) => write!(f, "{e}"),
+            Self::OutputDirNotWritable { path, source } => write!(
+                f,
+                "Output directory {} is not writable ({source})",
+                path.display()
+            ),
+            Self::Other(e) => write!(f, "{e:#}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Git(e) => Some(e),
+            Self::OutputDirNotWritable { source, .. } => Some(source),
+            Self::Other(e) => Some(e.as_ref()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+    use std::error::Error as _;
+
+    #[test]
+    fn source_exposes_the_underlying_errors() {
+        let e = Error::OutputDirNotWritable {
+            path: PathBuf::from("out"),
+            source: io::ErrorKind::PermissionDenied.into(),
+        };
+        let source = e.source().and_then(|x| x.downcast_ref::<io::Error>());
+        assert_eq!(
+            Some(io::ErrorKind::PermissionDenied),
+            source.map(|x| x.kind())
+        );
+
+        let e = Error::Other(
+            Err::<(), _>(io::Error::from(io::ErrorKind::NotFound))
+                .context("Failed to write out/file")
+                .unwrap_err(),
+        );
+        let chain = std::iter::successors(e.source(), |&x| x.source()).collect::<Vec<_>>();
+        assert_eq!(2, chain.len());
+        assert!(chain[1].downcast_ref::<io::Error>().is_some());
+
+        let e = Error::Git(git::Error::GitNotFound {
+            path: PathBuf::from("git"),
+        });
+        assert!(e
+            .source()
+            .and_then(|x| x.downcast_ref::<git::Error>())
+            .is_some());
+    }
+}
@@ -0,0 +1,290 @@
+use crate::git::FileChange;
+use crate::merge::MergeEntry;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Where a copied file was read from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CopySource {
+    /// A file in a worktree or in the working tree
+    File(PathBuf),
+
+    /// A blob read with `git cat-file`. An empty `rev` is the index.
+    Blob { rev: String, path: String },
+}
+
+impl Display for CopySource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::File(path) => write!(f, "{}", path.display()),
+            Self::Blob { rev, path } => write!(f, "{rev}:{path}"),
+        }
+    }
+}
+
+/// Progress of a [`crate::FilesCopy`] or [`crate::AutoCopy`] run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// The changed files between `from` and `to` are known
+    Started {
+        from: String,
+        to: String,
+        changes: Vec<FileChange>,
+    },
+
+    /// A temporary worktree of `rev` was checked out
+    WorktreeCreated {
+        rev: String,
+        path: PathBuf,
+    },
+
+    /// The files of `rev` are about to be copied
+    Extracting {
+        rev: String,
+    },
+
+    FileCopied {
+        source: CopySource,
+        dest: PathBuf,
+    },
+
+    /// Any other output file, such as the manifest, a script or a checksum list
+    FileWritten {
+        path: PathBuf,
+    },
+
+    MergeStarted {
+        target: PathBuf,
+    },
+
+    FileMerged {
+        entry: MergeEntry,
+    },
+
+    MergeFinished {
+        unresolved: usize,
+    },
+
+    PatchesStarted,
+
+    PatchWritten {
+        path: PathBuf,
+        binary: bool,
+    },
+
+    ArchiveStarted {
+        path: PathBuf,
+    },
+
+    ArchiveWritten {
+        path: PathBuf,
+    },
+
+    /// The run is done
+    Finished {
+        duration_ms: u64,
+    },
+
+    /// The branch selection of an auto run is about to start
+    AutoStarted {
+        base: String,
+        days: u64,
+        output_dir: PathBuf,
+    },
+
+    BranchSkipped {
+        name: String,
+        reason: String,
+    },
+
+    BranchesSelected {
+        count: usize,
+    },
+
+    /// A branch is about to be copied into `output_dir`
+    BranchStarted {
+        name: String,
+        output_dir: PathBuf,
+    },
+
+    /// An event of the copy of a branch. Branches processed in parallel interleave.
+    Branch {
+        name: String,
+        event: Box<Event>,
+    },
+
+    /// A branch is done. `error` is set if it failed.
+    BranchFinished {
+        name: String,
+        error: Option<String>,
+    },
+
+    /// Every selected branch is done
+    BranchesFinished {
+        succeeded: usize,
+        failed: Vec<String>,
+    },
+}
+
+/// Receives the [`Event`]s of a run
+pub trait Observer {
+    fn notify(&mut self, event: Event) -> Result<()>;
+}
+
+/// Renders events as the human-readable text of the command line.
+/// The events of a branch are printed as one block when the branch is done.
+pub struct TextObserver<W: Write> {
+    w: W,
+    branches: HashMap<String, Vec<u8>>,
+}
+
+impl<W: Write> TextObserver<W> {
+    pub fn new(w: W) -> Self {
+        Self {
+            w,
+            branches: HashMap::new(),
+        }
+    }
+
+    fn render<T: Write>(w: &mut T, event: &Event) -> Result<()> {
+        match event {
+            Event::Started { from, to, changes } if changes.is_empty() => {
+                writeln!(
+                    w,
+                    "There are no files with differences between {from} and {to}"
+                )?;
+            }
+            Event::Started { from, to, changes } => {
+                writeln!(w, "Updated files between {from} and {to}:")?;
+                for change in changes.iter() {
+                    writeln!(w, "\t{change}")?;
+                }
+            }
+            Event::Extracting { rev } => writeln!(w, "Copiying files from \"{rev}\"...")?,
+            Event::FileCopied { source, dest } => {
+                writeln!(w, "Copied: {source} -> {}", dest.display())?
+            }
+            Event::FileWritten { path } => writeln!(w, "Wrote: {}", path.display())?,
+            Event::MergeStarted { target } => writeln!(w, "Merging with {}...", target.display())?,
+            Event::FileMerged { entry } => writeln!(w, "\t{entry}")?,
+            Event::MergeFinished { unresolved } if *unresolved > 0 => {
+                writeln!(w, "{unresolved} file(s) need manual resolution")?
+            }
+            Event::PatchesStarted => writeln!(w, "Writing patches...")?,
+            Event::PatchWritten { path, binary } => {
+                if *binary {
+                    writeln!(w, "Patched: {} (binary)", path.display())?
+                } else {
+                    writeln!(w, "Patched: {}", path.display())?
+                }
+            }
+            Event::ArchiveStarted { path } => writeln!(w, "Packing {}...", path.display())?,
+            Event::ArchiveWritten { path } => writeln!(w, "Packed: {}", path.display())?,
+            Event::AutoStarted {
+                base,
+                days,
+                output_dir,
+            } => {
+                writeln!(w, "Base commit: {base}")?;
+                writeln!(w, "Days: {days}")?;
+                writeln!(w, "Output directory: {}", output_dir.display())?;
+            }
+            Event::BranchSkipped { name, reason } => {
+                writeln!(w, "Skipped branch: {name} ({reason})")?
+            }
+            Event::BranchesSelected { count } => writeln!(w, "Selected branch count: {count}")?,
+            Event::BranchesFinished { succeeded, failed } => {
+                writeln!(w, "Succeeded branch count: {succeeded}")?;
+                writeln!(w, "Failed branch count: {}", failed.len())?;
+                for name in failed.iter() {
+                    writeln!(w, "\t{name}")?;
+                }
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+}
+
+impl<W: Write> Observer for TextObserver<W> {
+    fn notify(&mut self, event: Event) -> Result<()> {
+        match event {
+            Event::BranchStarted { name, output_dir } => {
+                let buf = self.branches.entry(name.clone()).or_default();
+                writeln!(buf, "Processed branch: {name} -> {}", output_dir.display())?;
+            }
+            Event::Branch { name, event } => {
+                Self::render(self.branches.entry(name).or_default(), &event)?;
+            }
+            Event::BranchFinished { name, error } => {
+                if let Some(buf) = self.branches.remove(&name) {
+                    self.w.write_all(&buf)?;
+                }
+                if let Some(e) = error {
+                    writeln!(self.w, "Failed branch: {name} ({e})")?;
+                }
+            }
+            event => Self::render(&mut self.w, &event)?,
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn branch(name: &str, event: Event) -> Event {
+        Event::Branch {
+            name: name.to_string(),
+            event: Box::new(event),
+        }
+    }
+
+    #[test]
+    fn text_observer_prints_each_branch_as_one_block() {
+        let mut out = Vec::new();
+        let mut observer = TextObserver::new(&mut out);
+        for name in ["a", "b"] {
+            observer
+                .notify(Event::BranchStarted {
+                    name: name.to_string(),
+                    output_dir: PathBuf::from(name),
+                })
+                .unwrap();
+        }
+        for name in ["b", "a"] {
+            observer
+                .notify(branch(
+                    name,
+                    Event::FileWritten {
+                        path: PathBuf::from(format!("{name}.txt")),
+                    },
+                ))
+                .unwrap();
+        }
+        observer
+            .notify(Event::BranchFinished {
+                name: "b".to_string(),
+                error: Some("broken".to_string()),
+            })
+            .unwrap();
+        observer
+            .notify(Event::BranchFinished {
+                name: "a".to_string(),
+                error: None,
+            })
+            .unwrap();
+
+        assert_eq!(
+            "Processed branch: b -> b\nWrote: b.txt\nFailed branch: b (broken)\n\
+             Processed branch: a -> a\nWrote: a.txt\n",
+            String::from_utf8(out).unwrap()
+        );
+    }
+}
@@ -1,16 +1,181 @@
+use std::fmt::Display;
 use std::io;
+use std::path::PathBuf;
+use std::process::{Command, Output};
 use std::string::FromUtf8Error;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// A git command that exited unsuccessfully
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandFailure {
+    /// The command line, such as `git rev-parse main`
+    pub command: String,
+
+    /// The exit code, or `None` if git was terminated by a signal
+    pub status: Option<i32>,
+
+    pub stderr: String,
+}
+
+impl CommandFailure {
+    pub fn new(command: &Command, output: &Output) -> Self {
+        let command = std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|x| {
+                let x = x.to_string_lossy();
+                if x.is_empty() || x.contains(char::is_whitespace) {
+                    format!("\"{x}\"")
+                } else {
+                    x.into_owned()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        Self {
+            command,
+            status: output.status.code(),
+            stderr: String::from_utf8_lossy(&output.stderr)
+                .trim_end()
+                .to_string(),
+        }
+    }
+}
+
+impl Display for CommandFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.status {
+            Some(code) => write!(f, "`{}` exited with status {code}", self.command)?,
+            None => write!(f, "`{}` was terminated by a signal", self.command)?,
+        }
+        if !self.stderr.is_empty() {
+            write!(f, ": {}", self.stderr)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug)]
 pub enum Error {
     Io(io::Error),
     FromUtf8(FromUtf8Error),
-    Command(String),
+
+    /// The git executable could not be started
+    GitNotFound {
+        path: PathBuf,
+    },
+
+    /// The directory is not inside a git repository
+    NotARepository {
+        path: PathBuf,
+        failure: CommandFailure,
+    },
+
+    /// A commit, branch or tag that does not exist
+    UnknownRevision {
+        rev: String,
+        failure: CommandFailure,
+    },
+
+    /// `git worktree add` failed
+    WorktreeAdd {
+        rev: String,
+        path: PathBuf,
+        failure: CommandFailure,
+    },
+
+    /// Git refused to overwrite local changes to these paths
+    DirtyPathConflict {
+        paths: Vec<String>,
+        failure: CommandFailure,
+    },
+
+    /// Any other failed git command
+    Command {
+        message: String,
+        failure: CommandFailure,
+    },
+
+    /// Git printed something that could not be understood
+    Parse(String),
     LogParse(String),
 }
 
+impl Error {
+    /// Classifies the failure of `command` from what git printed to stderr.
+    /// `message` describes what was being done and is used when nothing more specific is known.
+    pub fn command(message: impl Into<String>, command: &Command, output: &Output) -> Self {
+        let failure = CommandFailure::new(command, output);
+        let path = command
+            .get_current_dir()
+            .map(PathBuf::from)
+            .unwrap_or_default();
+        Self::classify(message.into(), path, failure)
+    }
+
+    fn classify(message: String, path: PathBuf, failure: CommandFailure) -> Self {
+        let stderr = failure.stderr.as_str();
+        if stderr.contains("not a git repository") {
+            return Self::NotARepository { path, failure };
+        }
+        if stderr.contains("would be overwritten") {
+            // Git lists the paths indented by a tab
+            let paths = stderr
+                .lines()
+                .filter_map(|x| x.strip_prefix('\t'))
+                .map(|x| x.trim().to_string())
+                .collect();
+            return Self::DirtyPathConflict { paths, failure };
+        }
+        if let Some(rev) = unknown_revision(stderr) {
+            return Self::UnknownRevision { rev, failure };
+        }
+        Self::Command { message, failure }
+    }
+
+    /// The failed git command, if there was one
+    pub fn failure(&self) -> Option<&CommandFailure> {
+        match self {
+            Self::NotARepository { failure, .. }
+            | Self::UnknownRevision { failure, .. }
+            | Self::WorktreeAdd { failure, .. }
+            | Self::DirtyPathConflict { failure, .. }
+            | Self::Command { failure, .. } => Some(failure),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the revision named in an "unknown revision" message of git, which is empty when git
+/// does not name it
+fn unknown_revision(stderr: &str) -> Option<String> {
+    let quoted = |line: &str| {
+        line.split('\'')
+            .nth(1)
+            .map(|x| x.to_string())
+            .unwrap_or_default()
+    };
+    for line in stderr.lines() {
+        let line = line.strip_prefix("fatal: ").unwrap_or(line);
+        if line.contains("unknown revision") || line.starts_with("bad revision") {
+            return Some(quoted(line));
+        }
+        if line.starts_with("Needed a single revision") {
+            return Some(String::new());
+        }
+        for prefix in [
+            "invalid reference: ",
+            "Not a valid object name ",
+            "bad object ",
+        ] {
+            if let Some(rev) = line.strip_prefix(prefix) {
+                return Some(rev.trim().to_string());
+            }
+        }
+    }
+    None
+}
+
 impl From<io::Error> for Error {
     fn from(value: io::Error) -> Self {
         Self::Io(value)
@@ -28,10 +193,96 @@ impl std::fmt::Display for Error {
         match self {
             Self::Io(e) => write!(f, "{e}"),
             Self::FromUtf8(e) => write!(f, "{e}"),
-            Self::Command(e) => write!(f, "{e}"),
+            Self::GitNotFound { path } => {
+                write!(f, "Git executable not found ({})", path.display())
+            }
+            Self::NotARepository { path, failure } => {
+                write!(f, "Not a git repository: {} ({failure})", path.display())
+            }
+            Self::UnknownRevision { rev, failure } if rev.is_empty() => {
+                write!(f, "Unknown revision ({failure})")
+            }
+            Self::UnknownRevision { rev, failure } => {
+                write!(f, "Unknown revision {rev} ({failure})")
+            }
+            Self::WorktreeAdd { rev, path, failure } => write!(
+                f,
+                "Failed to add a worktree of {rev} at {} ({failure})",
+                path.display()
+            ),
+            Self::DirtyPathConflict { paths, failure } => write!(
+                f,
+                "Local changes would be overwritten: {} ({failure})",
+                paths.join(", ")
+            ),
+            Self::Command { message, failure } => write!(f, "{message} ({failure})"),
+            Self::Parse(e) => write!(f, "Could not parse \"{e}\""),
             Self::LogParse(e) => write!(f, "Could not parse \"{e}\""),
         }
     }
 }
 
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::FromUtf8(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn failure(stderr: &str) -> CommandFailure {
+        CommandFailure {
+            command: "git checkout main".into(),
+            status: Some(128),
+            stderr: stderr.into(),
+        }
+    }
+
+    fn classify(stderr: &str) -> Error {
+        Error::classify("Failed".into(), PathBuf::from("/repo"), failure(stderr))
+    }
+
+    #[test]
+    fn classifies_failures_from_stderr() {
+        assert!(matches!(
+            classify("fatal: not a git repository (or any of the parent directories): .git"),
+            Error::NotARepository { path, .. } if path == Path::new("/repo")
+        ));
+        assert!(matches!(
+            classify(
+                "fatal: ambiguous argument 'nope': unknown revision or path not in the working tree."
+            ),
+            Error::UnknownRevision { rev, .. } if rev == "nope"
+        ));
+        assert!(matches!(
+            classify("fatal: invalid reference: nope"),
+            Error::UnknownRevision { rev, .. } if rev == "nope"
+        ));
+        assert!(matches!(
+            classify(
+                "error: Your local changes to the following files would be overwritten by checkout:\n\
+                 \ta.txt\n\tdir/b.txt\nPlease commit your changes or stash them before you switch branches.\nAborting"
+            ),
+            Error::DirtyPathConflict { paths, .. } if paths == ["a.txt", "dir/b.txt"]
+        ));
+        assert!(matches!(
+            classify("fatal: something else"),
+            Error::Command { message, .. } if message == "Failed"
+        ));
+    }
+
+    #[test]
+    fn failure_shows_command_status_and_stderr() {
+        assert_eq!(
+            "`git checkout main` exited with status 128: fatal: oops",
+            failure("fatal: oops").to_string()
+        );
+    }
+}
@@ -0,0 +1,259 @@
+use super::Git;
+use super::{Error, Result};
+use std::collections::HashSet;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+/// Attributes that make a checkout write different bytes than the blob
+const FILTER_ATTRIBUTES: [&str; 6] = [
+    "filter",
+    "text",
+    "eol",
+    "crlf",
+    "ident",
+    "working-tree-encoding",
+];
+
+/// A blob read by [`GitCatFile::blobs`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Blob {
+    pub path: String,
+    pub oid: String,
+    pub contents: Vec<u8>,
+}
+
+pub struct GitCatFile {
+    inner: Git,
+    root_dir: PathBuf,
+    index_file: Option<PathBuf>,
+}
+
+impl GitCatFile {
+    pub fn new(git: impl AsRef<Path>, target_dir: impl AsRef<Path>) -> Result<Self> {
+        let git = Git::from_path(git)?;
+        let root_dir = git.get_rootdir(target_dir.as_ref())?;
+        Ok(Self {
+            inner: git,
+            root_dir,
+            index_file: None,
+        })
+    }
+
+    /// Reads attributes from this index file, such as one written by
+    /// [`super::GitReadTree::read_into`], instead of the index of the repository
+    pub fn with_index_file(mut self, index_file: Option<PathBuf>) -> Self {
+        self.index_file = index_file;
+        self
+    }
+
+    /// Streams the raw blobs of `paths` at `rev` (an empty `rev` reads the index) through a
+    /// single `git cat-file --batch`. Paths that are not blobs at `rev` are skipped.
+    /// An error of `f` stops the batch and is returned as it is.
+    pub fn blobs<E, F>(&self, rev: &str, paths: &[String], mut f: F) -> std::result::Result<(), E>
+    where
+        E: From<Error>,
+        F: FnMut(Blob) -> std::result::Result<(), E>,
+    {
+        let mut stopped = None;
+        let ret = self.read_blobs(rev, paths, |blob| {
+            f(blob).map_err(|e| {
+                stopped = Some(e);
+                Error::Io(io::Error::other("Stopped reading blobs"))
+            })
+        });
+        match stopped {
+            Some(e) => Err(e),
+            None => Ok(ret?),
+        }
+    }
+
+    fn read_blobs<F: FnMut(Blob) -> Result<()>>(
+        &self,
+        rev: &str,
+        paths: &[String],
+        mut f: F,
+    ) -> Result<()> {
+        if let Some(path) = paths.iter().find(|x| x.contains('\n')) {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Failed to read \"{path}\" (paths with newlines are not supported)"),
+            )));
+        }
+
+        let mut command = self.inner.command(&self.root_dir);
+        command
+            .args([
+                "cat-file",
+                "--batch=%(objectname) %(objecttype) %(objectsize)",
+            ])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let mut child = command.spawn()?;
+        let mut stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| io::Error::other("Failed to open stdin of cat-file"))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| io::Error::other("Failed to open stdout of cat-file"))?;
+
+        let ret = std::thread::scope(|scope| {
+            // Write the requests on another thread so a full stdout pipe cannot block them
+            let writer = scope.spawn(move || -> std::io::Result<()> {
+                for path in paths.iter() {
+                    writeln!(stdin, "{rev}:{path}")?;
+                }
+                Ok(())
+            });
+            let ret = Self::read_batch(BufReader::new(stdout), paths, &mut f);
+            writer
+                .join()
+                .map_err(|_| io::Error::other("Failed to write to cat-file"))??;
+            ret
+        });
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(Error::command(
+                format!("Failed to read blobs at {rev}"),
+                &command,
+                &output,
+            ));
+        }
+
+        ret
+    }
+
+    fn read_batch<R: BufRead, F: FnMut(Blob) -> Result<()>>(
+        mut reader: R,
+        paths: &[String],
+        f: &mut F,
+    ) -> Result<()> {
+        for path in paths.iter() {
+            let mut header = String::new();
+            reader.read_line(&mut header)?;
+            let header = header.trim_end_matches('\n');
+            if header.ends_with(" missing") || header.ends_with(" ambiguous") {
+                continue;
+            }
+
+            let mut fields = header.split(' ');
+            let (Some(oid), Some(kind), Some(size)) = (fields.next(), fields.next(), fields.next())
+            else {
+                return Err(Error::Parse(header.to_string()));
+            };
+            let size = size
+                .parse::<usize>()
+                .map_err(|_| Error::Parse(header.to_string()))?;
+            // Every object is followed by a newline
+            let mut contents = vec![0; size + 1];
+            reader.read_exact(&mut contents)?;
+            contents.truncate(size);
+            if kind == "blob" {
+                f(Blob {
+                    path: path.clone(),
+                    oid: oid.to_string(),
+                    contents,
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the paths whose checkout may differ from the raw blob because of attributes or
+    /// `core.autocrlf`. The attributes are read from the index (see [`GitCatFile::with_index_file`]),
+    /// not from the working tree.
+    pub fn filtered_paths(&self, paths: &[String]) -> Result<HashSet<String>> {
+        if paths.is_empty() {
+            return Ok(HashSet::new());
+        }
+        if self.autocrlf()? {
+            return Ok(paths.iter().cloned().collect());
+        }
+
+        let mut command = self.inner.command(&self.root_dir);
+        command
+            .args(["check-attr", "-z", "--stdin", "--cached"])
+            .args(FILTER_ATTRIBUTES)
+            .envs(self.index_file.iter().map(|x| ("GIT_INDEX_FILE", x)))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let mut child = command.spawn()?;
+        let mut stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| io::Error::other("Failed to open stdin of check-attr"))?;
+        let writer = std::thread::spawn({
+            let paths = paths.to_vec();
+            move || -> std::io::Result<()> {
+                for path in paths.iter() {
+                    stdin.write_all(path.as_bytes())?;
+                    stdin.write_all(b"\0")?;
+                }
+                Ok(())
+            }
+        });
+        let output = child.wait_with_output()?;
+        writer
+            .join()
+            .map_err(|_| io::Error::other("Failed to write to check-attr"))??;
+
+        if !output.status.success() {
+            return Err(Error::command(
+                "Failed to check attributes",
+                &command,
+                &output,
+            ));
+        }
+
+        // "<path>\0<attribute>\0<value>\0" for every path and attribute
+        let stdout = String::from_utf8(output.stdout)?;
+        let fields = stdout.split('\0').collect::<Vec<_>>();
+        Ok(fields
+            .chunks_exact(3)
+            .filter(|x| x[2] != "unspecified" && x[2] != "unset")
+            .map(|x| x[0].to_string())
+            .collect())
+    }
+
+    fn autocrlf(&self) -> Result<bool> {
+        let mut command = self.inner.command(&self.root_dir);
+        command
+            .args(["config", "--type=bool", "--get", "core.autocrlf"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let output = command.output()?;
+
+        // "input" only converts on commit and is not a bool, and an unset key exits with 1
+        Ok(output.status.success() && String::from_utf8(output.stdout)?.trim() == "true")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_batch_frames_objects_and_skips_missing() {
+        let output = b"aaa blob 3\nab\n\nbbb missing\nccc tree 2\n\0\0\nddd blob 0\n\n";
+        let paths = ["a", "b", "c", "d"].map(String::from);
+        let mut blobs = Vec::new();
+
+        GitCatFile::read_batch(&output[..], &paths, &mut |blob| {
+            blobs.push(blob);
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(2, blobs.len());
+        assert_eq!("a", blobs[0].path);
+        assert_eq!("aaa", blobs[0].oid);
+        assert_eq!(b"ab\n".to_vec(), blobs[0].contents);
+        assert_eq!("d", blobs[1].path);
+        assert!(blobs[1].contents.is_empty());
+    }
+}
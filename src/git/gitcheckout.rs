@@ -1,7 +1,7 @@
 use super::Git;
 use super::{Error, Result};
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::Stdio;
 
 pub struct GitCheckout {
     inner: Git,
@@ -25,19 +25,19 @@ impl GitCheckout {
     }
 
     pub fn checkout(&self, path: &str) -> Result<PathBuf> {
-        self.inner.exec(&self.root_dir, |git| {
-            let args = vec!["checkout", &self.commit, path];
-            let output = Command::new(git)
-                .args(args)
-                .stderr(Stdio::piped())
-                .output()?;
-            let stderr = String::from_utf8(output.stderr)?;
+        let args = vec!["checkout", &self.commit, path];
+        let mut command = self.inner.command(&self.root_dir);
+        command.args(args).stderr(Stdio::piped());
+        let output = command.output()?;
 
-            if !output.status.success() {
-                return Err(Error::Command(stderr));
-            }
+        if !output.status.success() {
+            return Err(Error::command(
+                format!("Failed to check out {path} at {}", self.commit),
+                &command,
+                &output,
+            ));
+        }
 
-            Ok(self.root_dir.join(path))
-        })
+        Ok(self.root_dir.join(path))
     }
 }
@@ -0,0 +1,76 @@
+use super::Git;
+use super::{Error, Result};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+pub struct GitCheckoutIndex {
+    inner: Git,
+    root_dir: PathBuf,
+    index_file: Option<PathBuf>,
+    work_tree: Option<PathBuf>,
+}
+
+impl GitCheckoutIndex {
+    pub fn new(git: impl AsRef<Path>, target_dir: impl AsRef<Path>) -> Result<Self> {
+        let git = Git::from_path(git)?;
+        let root_dir = git.get_rootdir(target_dir.as_ref())?;
+        Ok(Self {
+            inner: git,
+            root_dir,
+            index_file: None,
+            work_tree: None,
+        })
+    }
+
+    /// Reads this index file, such as one written by [`super::GitReadTree::read_into`], instead
+    /// of the index of the repository
+    pub fn with_index_file(mut self, index_file: Option<PathBuf>) -> Self {
+        self.index_file = index_file;
+        self
+    }
+
+    /// Runs git with this working tree. With an empty directory the attributes are read from the
+    /// index only.
+    pub fn with_work_tree(mut self, work_tree: Option<PathBuf>) -> Self {
+        self.work_tree = work_tree;
+        self
+    }
+
+    /// Writes the staged contents of `paths` under `dest_dir`
+    pub fn checkout_to(&self, dest_dir: impl AsRef<Path>, paths: &[String]) -> Result<()> {
+        // Git runs in the root directory
+        let mut prefix = std::path::absolute(dest_dir)?.display().to_string();
+        if !prefix.ends_with('/') {
+            prefix.push('/');
+        }
+        let mut command = self.inner.command(&self.root_dir);
+        command
+            .args(["checkout-index", "--force", "-z", "--stdin"])
+            .arg(format!("--prefix={prefix}"))
+            .envs(self.index_file.iter().map(|x| ("GIT_INDEX_FILE", x)))
+            .envs(self.work_tree.iter().map(|x| ("GIT_WORK_TREE", x)))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let mut child = command.spawn()?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            for path in paths.iter() {
+                stdin.write_all(path.as_bytes())?;
+                stdin.write_all(b"\0")?;
+            }
+        }
+        let output = child.wait_with_output()?;
+
+        if !output.status.success() {
+            return Err(Error::command(
+                "Failed to check out the index",
+                &command,
+                &output,
+            ));
+        }
+
+        Ok(())
+    }
+}
@@ -1,7 +1,122 @@
 use super::Git;
 use super::{Error, Result};
+use std::fmt::Display;
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::Stdio;
+
+/// A file that differs between the two commits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileChange {
+    Added(String),
+    Modified(String),
+    Deleted(String),
+    Renamed {
+        from: String,
+        to: String,
+        similarity: u8,
+    },
+    Copied {
+        from: String,
+        to: String,
+        similarity: u8,
+    },
+    TypeChanged(String),
+}
+
+impl FileChange {
+    /// The path of the file at the "from" commit, if it exists there
+    pub fn from_path(&self) -> Option<&str> {
+        match self {
+            Self::Added(_) | Self::Copied { .. } => None,
+            Self::Modified(path) | Self::Deleted(path) | Self::TypeChanged(path) => Some(path),
+            Self::Renamed { from, .. } => Some(from),
+        }
+    }
+
+    /// The path of the file at the "to" commit, if it exists there
+    pub fn to_path(&self) -> Option<&str> {
+        match self {
+            Self::Deleted(_) => None,
+            Self::Added(path) | Self::Modified(path) | Self::TypeChanged(path) => Some(path),
+            Self::Renamed { to, .. } | Self::Copied { to, .. } => Some(to),
+        }
+    }
+
+    /// The status letter of `git diff --name-status`
+    pub fn status(&self) -> char {
+        match self {
+            Self::Added(_) => 'A',
+            Self::Modified(_) => 'M',
+            Self::Deleted(_) => 'D',
+            Self::Renamed { .. } => 'R',
+            Self::Copied { .. } => 'C',
+            Self::TypeChanged(_) => 'T',
+        }
+    }
+
+    /// Parse from the output of `git diff --name-status -z`
+    pub(super) fn parse_all(s: &str) -> Result<Vec<Self>> {
+        let mut fields = s.split('\0').filter(|x| !x.is_empty());
+        let mut ret = Vec::new();
+        while let Some(status) = fields.next() {
+            let mut path = || {
+                fields
+                    .next()
+                    .map(|x| x.to_string())
+                    .ok_or_else(|| Error::Parse(status.to_string()))
+            };
+            let change = match status.chars().next() {
+                Some('A') => Self::Added(path()?),
+                Some('M') => Self::Modified(path()?),
+                Some('D') => Self::Deleted(path()?),
+                Some('T') => Self::TypeChanged(path()?),
+                Some('R') => Self::Renamed {
+                    similarity: Self::parse_similarity(status)?,
+                    from: path()?,
+                    to: path()?,
+                },
+                Some('C') => Self::Copied {
+                    similarity: Self::parse_similarity(status)?,
+                    from: path()?,
+                    to: path()?,
+                },
+                _ => return Err(Error::Parse(status.to_string())),
+            };
+            ret.push(change);
+        }
+
+        Ok(ret)
+    }
+
+    /// Parse the score of "R085" or "C100"
+    fn parse_similarity(status: &str) -> Result<u8> {
+        status
+            .get(1..)
+            .and_then(|x| x.parse::<u8>().ok())
+            .ok_or_else(|| Error::Parse(status.to_string()))
+    }
+}
+
+impl Display for FileChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Added(path) => write!(f, "added: {path}"),
+            Self::Modified(path) => write!(f, "modified: {path}"),
+            Self::Deleted(path) => write!(f, "deleted: {path}"),
+            Self::Renamed {
+                from,
+                to,
+                similarity,
+            } => write!(f, "renamed: {from} -> {to} ({similarity}%)"),
+            Self::Copied {
+                from,
+                to,
+                similarity,
+            } => write!(f, "copied: {from} -> {to} ({similarity}%)"),
+            Self::TypeChanged(path) => write!(f, "typechange: {path}"),
+        }
+    }
+}
 
 enum StagedOption {
     NotStaged,
@@ -13,6 +128,8 @@ pub struct GitDiff {
     from: String,
     to: Option<String>,
     root_dir: PathBuf,
+    pathspecs: Vec<String>,
+    staged: bool,
 }
 
 impl GitDiff {
@@ -29,9 +146,36 @@ impl GitDiff {
             from: from.into(),
             to: to.map(Into::into),
             root_dir,
+            pathspecs: Vec::new(),
+            staged: false,
         })
     }
 
+    /// Compares against the index instead of the working tree when there is no `to` commit
+    pub fn with_staged(mut self, staged: bool) -> Self {
+        self.staged = staged;
+        self
+    }
+
+    fn push_staged(&self, args: &mut Vec<&str>) {
+        if self.staged {
+            args.push("--staged");
+        }
+    }
+
+    /// Limits the differences to the paths matching these pathspecs
+    pub fn with_pathspecs(mut self, pathspecs: impl IntoIterator<Item = String>) -> Self {
+        self.pathspecs = pathspecs.into_iter().collect();
+        self
+    }
+
+    fn push_pathspecs<'a>(&'a self, args: &mut Vec<&'a str>) {
+        if !self.pathspecs.is_empty() {
+            args.push("--");
+            args.extend(self.pathspecs.iter().map(|x| x.as_str()));
+        }
+    }
+
     pub fn name_only(&self) -> Result<Vec<String>> {
         self.inner_name_only(StagedOption::NotStaged)
     }
@@ -41,34 +185,160 @@ impl GitDiff {
     }
 
     fn inner_name_only(&self, staged: StagedOption) -> Result<Vec<String>> {
-        self.inner.exec(&self.root_dir, |git| {
-            let mut args = vec!["diff"];
-            if let StagedOption::Staged = staged {
-                args.push("--staged");
-            }
-            args.extend(vec!["--name-only", &self.from]);
-            if let Some(to) = self.to.as_ref() {
-                args.push(to);
-            }
-            let output = Command::new(git)
-                .args(args)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .output()?;
-            let stdout = String::from_utf8(output.stdout)?;
-            let stderr = String::from_utf8(output.stderr)?;
-
-            if !output.status.success() {
-                return Err(Error::Command(format!(
-                    "Failed to get differences ({stderr})"
-                )));
-            }
-
-            Ok(stdout
-                .split('\n')
-                .filter(|x| !x.is_empty())
-                .map(|x| x.into())
-                .collect::<Vec<_>>())
-        })
+        let mut args = vec!["diff"];
+        if let StagedOption::Staged = staged {
+            args.push("--staged");
+        }
+        args.extend(vec!["--name-only", &self.from]);
+        if let Some(to) = self.to.as_ref() {
+            args.push(to);
+        }
+        self.push_pathspecs(&mut args);
+        let mut command = self.inner.command(&self.root_dir);
+        command
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let output = command.output()?;
+
+        if !output.status.success() {
+            return Err(Error::command(
+                "Failed to get differences",
+                &command,
+                &output,
+            ));
+        }
+
+        let stdout = String::from_utf8(output.stdout)?;
+
+        Ok(stdout
+            .split('\n')
+            .filter(|x| !x.is_empty())
+            .map(|x| x.into())
+            .collect::<Vec<_>>())
+    }
+
+    pub fn name_status(&self) -> Result<Vec<FileChange>> {
+        let mut args = vec!["diff"];
+        self.push_staged(&mut args);
+        args.extend(["--name-status", "-z", "-M", "-C", &self.from]);
+        if let Some(to) = self.to.as_ref() {
+            args.push(to);
+        }
+        self.push_pathspecs(&mut args);
+        let mut command = self.inner.command(&self.root_dir);
+        command
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let output = command.output()?;
+
+        if !output.status.success() {
+            return Err(Error::command(
+                "Failed to get differences",
+                &command,
+                &output,
+            ));
+        }
+
+        let stdout = String::from_utf8(output.stdout)?;
+
+        FileChange::parse_all(&stdout)
+    }
+
+    pub fn patch(&self, paths: &[&str]) -> Result<Vec<u8>> {
+        let mut args = vec!["diff"];
+        self.push_staged(&mut args);
+        args.extend(["--no-color", "--no-ext-diff", "-M", "-C", &self.from]);
+        if let Some(to) = self.to.as_ref() {
+            args.push(to);
+        }
+        if paths.is_empty() {
+            self.push_pathspecs(&mut args);
+        } else {
+            args.push("--");
+            args.extend(paths);
+        }
+        let mut command = self.inner.command(&self.root_dir);
+        command
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let output = command.output()?;
+
+        if !output.status.success() {
+            return Err(Error::command("Failed to get patch", &command, &output));
+        }
+
+        Ok(output.stdout)
+    }
+
+    pub fn is_binary(&self, path: &str) -> Result<bool> {
+        let mut args = vec!["diff"];
+        self.push_staged(&mut args);
+        args.extend(["--numstat", &self.from]);
+        if let Some(to) = self.to.as_ref() {
+            args.push(to);
+        }
+        args.extend(["--", path]);
+        let mut command = self.inner.command(&self.root_dir);
+        command
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let output = command.output()?;
+
+        if !output.status.success() {
+            return Err(Error::command(
+                format!("Failed to get numstat of {path}"),
+                &command,
+                &output,
+            ));
+        }
+
+        let stdout = String::from_utf8(output.stdout)?;
+
+        // Binary files are reported as "-\t-\t<path>"
+        Ok(stdout.lines().any(|x| x.starts_with("-\t-\t")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_name_status() {
+        let source = "M\0changed.txt\0A\0dir/added file.txt\0D\0deleted.txt\0R085\0old.txt\0new.txt\0C100\0src.txt\0copy.txt\0T\0link\0";
+        let changes = FileChange::parse_all(source).unwrap();
+        assert_eq!(
+            vec![
+                FileChange::Modified("changed.txt".into()),
+                FileChange::Added("dir/added file.txt".into()),
+                FileChange::Deleted("deleted.txt".into()),
+                FileChange::Renamed {
+                    from: "old.txt".into(),
+                    to: "new.txt".into(),
+                    similarity: 85,
+                },
+                FileChange::Copied {
+                    from: "src.txt".into(),
+                    to: "copy.txt".into(),
+                    similarity: 100,
+                },
+                FileChange::TypeChanged("link".into()),
+            ],
+            changes
+        );
+        assert_eq!(None, changes[4].from_path());
+        assert_eq!(Some("copy.txt"), changes[4].to_path());
+        assert_eq!("renamed: old.txt -> new.txt (85%)", changes[3].to_string());
+    }
+
+    #[test]
+    fn test_parse_name_status_rejects_truncated_output() {
+        assert!(FileChange::parse_all("R100\0old.txt\0").is_err());
+        assert!(FileChange::parse_all("X\0unknown\0").is_err());
+        assert!(FileChange::parse_all("").unwrap().is_empty());
     }
 }
@@ -1,15 +1,81 @@
 use super::Git;
 use super::{Error, Result};
+use std::fmt::Display;
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::Stdio;
+use std::str::FromStr;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct GitLocalBranch {
     pub name: String,
+
+    /// The full ref, such as `refs/remotes/origin/main`
+    pub ref_name: String,
+
     pub head_hash: String,
+    pub short_hash: String,
     pub committer_timestamp: i64,
 }
 
+/// Which refs are listed as branch candidates
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RefSource {
+    /// `refs/heads`
+    Local,
+    /// `refs/remotes/<remote>`
+    Remote(String),
+    /// `refs/tags`
+    Tags,
+    /// Any `git for-each-ref` pattern, e.g. `refs/remotes/origin/release`
+    Pattern(String),
+}
+
+impl RefSource {
+    /// The `git for-each-ref` pattern of this source
+    pub fn pattern(&self) -> String {
+        match self {
+            Self::Local => "refs/heads".to_string(),
+            Self::Remote(remote) => format!("refs/remotes/{remote}"),
+            Self::Tags => "refs/tags".to_string(),
+            Self::Pattern(pattern) => pattern.clone(),
+        }
+    }
+}
+
+impl FromStr for RefSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "local" => Ok(Self::Local),
+            "remote" => Ok(Self::Remote("origin".to_string())),
+            "tags" => Ok(Self::Tags),
+            _ => {
+                if let Some(remote) = s.strip_prefix("remote:").filter(|x| !x.is_empty()) {
+                    Ok(Self::Remote(remote.to_string()))
+                } else if s.starts_with("refs/") {
+                    Ok(Self::Pattern(s.to_string()))
+                } else {
+                    Err(format!(
+                        "unknown ref source \"{s}\" (expected local, remote, remote:<NAME>, tags or a refs/ pattern)"
+                    ))
+                }
+            }
+        }
+    }
+}
+
+impl Display for RefSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Local => write!(f, "local"),
+            Self::Remote(remote) => write!(f, "remote:{remote}"),
+            Self::Tags => write!(f, "tags"),
+            Self::Pattern(pattern) => write!(f, "{pattern}"),
+        }
+    }
+}
+
 pub struct GitLocalBranches {
     inner: Git,
     root_dir: PathBuf,
@@ -26,47 +92,111 @@ impl GitLocalBranches {
     }
 
     pub fn list(&self) -> Result<Vec<GitLocalBranch>> {
-        self.inner.exec(&self.root_dir, |git| {
-            let output = Command::new(git)
-                .args([
-                    "for-each-ref",
-                    "refs/heads",
-                    "--format=%(refname:short)\t%(objectname)\t%(committerdate:unix)",
-                ])
-                .current_dir(&self.root_dir)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .output()?;
-
-            if !output.status.success() {
-                let stderr = String::from_utf8(output.stderr)?;
-                return Err(Error::Command(format!(
-                    "Failed to list local branches ({stderr})"
-                )));
-            }
+        self.list_refs(&RefSource::Local)
+    }
 
-            let stdout = String::from_utf8(output.stdout)?;
-            let mut branches = Vec::new();
-            for line in stdout.lines().filter(|line| !line.is_empty()) {
-                let fields = line.split('\t').collect::<Vec<_>>();
-                if fields.len() != 3 {
-                    return Err(Error::Command("Failed to parse local branches".to_string()));
-                }
+    /// Lists the refs of `source` that point to a commit. Annotated tags are peeled to their
+    /// commit, and symbolic refs such as `origin/HEAD` are skipped.
+    pub fn list_refs(&self, source: &RefSource) -> Result<Vec<GitLocalBranch>> {
+        let pattern = source.pattern();
+        let mut command = self.inner.command(&self.root_dir);
+        command
+            .args([
+                "for-each-ref",
+                &pattern,
+                "--format=%(refname:short)\t%(refname)\t%(symref)\t%(objectname)\t%(objectname:short)\t%(committerdate:unix)\t%(*objectname)\t%(*objectname:short)\t%(*committerdate:unix)",
+            ])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let output = command.output()?;
 
-                let timestamp = fields[2].parse::<i64>().map_err(|_| {
-                    Error::Command(format!(
-                        "Failed to parse branch timestamp for {}",
-                        fields[0]
-                    ))
-                })?;
-                branches.push(GitLocalBranch {
-                    name: fields[0].to_string(),
-                    head_hash: fields[1].to_string(),
-                    committer_timestamp: timestamp,
-                });
+        if !output.status.success() {
+            return Err(Error::command(
+                format!("Failed to list {pattern}"),
+                &command,
+                &output,
+            ));
+        }
+
+        let stdout = String::from_utf8(output.stdout)?;
+        let mut branches = Vec::new();
+        for line in stdout.lines().filter(|line| !line.is_empty()) {
+            let fields = line.split('\t').collect::<Vec<_>>();
+            if fields.len() != 9 {
+                return Err(Error::Parse(line.to_string()));
+            }
+            if !fields[2].is_empty() {
+                continue;
             }
 
-            Ok(branches)
-        })
+            let (head_hash, short_hash, timestamp) = if fields[6].is_empty() {
+                (fields[3], fields[4], fields[5])
+            } else {
+                (fields[6], fields[7], fields[8])
+            };
+            // Only commits have a committer date
+            if timestamp.is_empty() {
+                continue;
+            }
+            let timestamp = timestamp
+                .parse::<i64>()
+                .map_err(|_| Error::Parse(line.to_string()))?;
+            branches.push(GitLocalBranch {
+                name: fields[0].to_string(),
+                ref_name: fields[1].to_string(),
+                head_hash: head_hash.to_string(),
+                short_hash: short_hash.to_string(),
+                committer_timestamp: timestamp,
+            });
+        }
+
+        Ok(branches)
+    }
+
+    /// Lists the names of the configured remotes, which may contain `/`
+    pub fn remotes(&self) -> Result<Vec<String>> {
+        let mut command = self.inner.command(&self.root_dir);
+        command
+            .arg("remote")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let output = command.output()?;
+
+        if !output.status.success() {
+            return Err(Error::command("Failed to list remotes", &command, &output));
+        }
+
+        let stdout = String::from_utf8(output.stdout)?;
+        Ok(stdout
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(Into::into)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ref_source_parses_cli_values() {
+        assert_eq!(Ok(RefSource::Local), "local".parse());
+        assert_eq!(Ok(RefSource::Remote("origin".into())), "remote".parse());
+        assert_eq!(
+            Ok(RefSource::Remote("upstream".into())),
+            "remote:upstream".parse()
+        );
+        assert_eq!(Ok(RefSource::Tags), "tags".parse());
+        assert_eq!(
+            Ok(RefSource::Pattern("refs/remotes/origin/release".into())),
+            "refs/remotes/origin/release".parse()
+        );
+        assert!("remote:".parse::<RefSource>().is_err());
+        assert!("branches".parse::<RefSource>().is_err());
+        assert_eq!(
+            "refs/remotes/upstream",
+            RefSource::Remote("upstream".into()).pattern()
+        );
     }
 }
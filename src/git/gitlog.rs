@@ -1,12 +1,16 @@
 use super::Git;
 use super::{Error, Result};
+use std::io::{self, BufRead, BufReader, Lines, Read};
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::{Child, ChildStdout, Command, Output, Stdio};
+use std::thread::{self, JoinHandle};
 
 pub struct GitLog {
     inner: Git,
     all: bool,
     root_dir: PathBuf,
+    max_count: Option<usize>,
+    since: Option<String>,
 }
 
 impl GitLog {
@@ -17,34 +21,235 @@ impl GitLog {
             inner: git,
             all,
             root_dir,
+            max_count: None,
+            since: None,
         })
     }
 
+    /// Limits the log to this many commits
+    pub fn with_max_count(mut self, max_count: Option<usize>) -> Self {
+        self.max_count = max_count;
+        self
+    }
+
+    /// Limits the log to the commits more recent than this date, such as `2024-01-01` or
+    /// `2 weeks ago`
+    pub fn with_since(mut self, since: Option<String>) -> Self {
+        self.since = since;
+        self
+    }
+
+    fn command(&self) -> Command {
+        let mut args = vec!["log".to_string(), "--graph".to_string()];
+        if self.all {
+            args.push("--all".into());
+        }
+        if let Some(max_count) = self.max_count {
+            args.push(format!("--max-count={max_count}"));
+        }
+        if let Some(since) = self.since.as_ref() {
+            args.push(format!("--since={since}"));
+        }
+        args.push("--pretty=format:%h -%d %s (%ci) <%an>".into());
+        args.push("--abbrev-commit".into());
+        args.push("--date=relative".into());
+        let mut command = self.inner.command(&self.root_dir);
+        command.args(args);
+        command
+    }
+
     pub fn tree(&self) -> Result<Vec<String>> {
-        self.inner.exec(&self.root_dir, |git| {
-            let mut args = vec!["log", "--graph"];
-            if self.all {
-                args.push("--all");
-            }
-            args.push("--pretty=format:%h -%d %s (%ci) <%an>");
-            args.push("--abbrev-commit");
-            args.push("--date=relative");
-            let output = Command::new(git)
-                .args(args)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .output()?;
-            let stdout = String::from_utf8(output.stdout)?;
-            let stderr = String::from_utf8(output.stderr)?;
-
-            if !output.status.success() {
-                return Err(Error::Command(format!("Failed to get logs ({stderr})")));
-            }
+        let mut command = self.command();
+        command.stdout(Stdio::piped()).stderr(Stdio::piped());
+        let output = command.output()?;
+
+        if !output.status.success() {
+            return Err(Error::command("Failed to get logs", &command, &output));
+        }
 
-            Ok(stdout
-                .split('\n')
-                .map(|x| x.to_string())
-                .collect::<Vec<_>>())
+        let stdout = String::from_utf8(output.stdout)?;
+
+        Ok(stdout
+            .split('\n')
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>())
+    }
+
+    /// Streams the lines of [`GitLog::tree`] as git prints them, so the caller can stop reading
+    /// early. `--graph` implies `--topo-order`, so unless the repository has a commit-graph file
+    /// git still walks the whole history before it prints the first line.
+    pub fn stream(&self) -> Result<LogLines> {
+        let mut command = self.command();
+        command.stdout(Stdio::piped()).stderr(Stdio::piped());
+        let mut child = command.spawn()?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| io::Error::other("Failed to open stdout of log"))?;
+        let mut stderr = child
+            .stderr
+            .take()
+            .ok_or_else(|| io::Error::other("Failed to open stderr of log"))?;
+        // Git would block on a full stderr pipe while stdout is read
+        let stderr = thread::spawn(move || {
+            let mut buf = Vec::new();
+            stderr.read_to_end(&mut buf).map(|_| buf)
+        });
+        Ok(LogLines {
+            command,
+            child,
+            lines: BufReader::new(stdout).lines(),
+            stderr: Some(stderr),
+            done: false,
         })
     }
 }
+
+/// The lines of a running `git log --graph`, returned by [`GitLog::stream`].
+/// Git is stopped when this is dropped before the last line.
+pub struct LogLines {
+    command: Command,
+    child: Child,
+    lines: Lines<BufReader<ChildStdout>>,
+    stderr: Option<JoinHandle<io::Result<Vec<u8>>>>,
+    done: bool,
+}
+
+impl LogLines {
+    /// Reads up to `n` lines. Fewer lines are returned only at the end of the log.
+    pub fn page(&mut self, n: usize) -> Result<Vec<String>> {
+        self.by_ref().take(n).collect()
+    }
+
+    /// Whether every line has been read
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.done = true;
+        let stderr = match self.stderr.take().map(|x| x.join()) {
+            Some(Ok(stderr)) => stderr?,
+            Some(Err(_)) => return Err(io::Error::other("Failed to read stderr of log").into()),
+            None => Vec::new(),
+        };
+        let output = Output {
+            status: self.child.wait()?,
+            stdout: Vec::new(),
+            stderr,
+        };
+        if !output.status.success() {
+            return Err(Error::command("Failed to get logs", &self.command, &output));
+        }
+
+        Ok(())
+    }
+}
+
+impl Iterator for LogLines {
+    type Item = Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.lines.next() {
+            Some(Ok(line)) => Some(Ok(line)),
+            Some(Err(e)) => {
+                self.done = true;
+                Some(Err(e.into()))
+            }
+            None => self.finish().err().map(Err),
+        }
+    }
+}
+
+impl Drop for LogLines {
+    fn drop(&mut self) {
+        if !self.done {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use outdir_tempdir::TempDir;
+
+    fn run_git(repo_dir: &Path, args: &[&str]) {
+        let output = std::process::Command::new("git")
+            .args(args)
+            .current_dir(repo_dir)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "git {:?} failed: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    fn commit_at(repo_dir: &Path, message: &str, date: &str) {
+        let output = std::process::Command::new("git")
+            .args(["commit", "-q", "--allow-empty", "-m", message])
+            .env("GIT_AUTHOR_DATE", date)
+            .env("GIT_COMMITTER_DATE", date)
+            .current_dir(repo_dir)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "git commit failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    #[test]
+    fn stream_reads_the_log_in_pages() {
+        let dir = TempDir::new().autorm();
+        let repo_dir = dir.path().to_path_buf();
+        run_git(&repo_dir, &["init", "-q"]);
+        run_git(&repo_dir, &["config", "user.name", "Test"]);
+        run_git(&repo_dir, &["config", "user.email", "test@example.com"]);
+        for i in 0..5 {
+            let date = if i < 2 {
+                "2020-01-01T00:00:00Z"
+            } else {
+                "2024-01-01T00:00:00Z"
+            };
+            commit_at(&repo_dir, &format!("c{i}"), date);
+        }
+
+        let log = GitLog::new("git", false, &repo_dir).unwrap();
+        let mut lines = log.stream().unwrap();
+        let first = lines.page(2).unwrap();
+        assert_eq!(2, first.len());
+        assert!(first[0].contains(" c4 "), "{first:?}");
+        assert!(!lines.is_done());
+        let rest = lines.page(10).unwrap();
+        assert_eq!(3, rest.len());
+        assert!(lines.is_done());
+        assert_eq!(log.tree().unwrap(), [first, rest].concat());
+
+        let log = log.with_max_count(Some(2));
+        assert_eq!(2, log.stream().unwrap().page(10).unwrap().len());
+
+        let log = GitLog::new("git", false, &repo_dir)
+            .unwrap()
+            .with_since(Some("2023-01-01".into()));
+        let recent = log.tree().unwrap();
+        assert_eq!(3, recent.len(), "{recent:?}");
+        assert!(recent[2].contains(" c2 "), "{recent:?}");
+
+        // Git fails only after it is started
+        run_git(&repo_dir, &["checkout", "-q", "--orphan", "empty"]);
+        let mut lines = GitLog::new("git", false, &repo_dir)
+            .unwrap()
+            .stream()
+            .unwrap();
+        assert!(matches!(lines.page(10), Err(Error::Command { .. })));
+    }
+}
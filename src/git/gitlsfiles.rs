@@ -0,0 +1,67 @@
+use super::Git;
+use super::{Error, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+pub struct GitLsFiles {
+    inner: Git,
+    root_dir: PathBuf,
+    index_file: Option<PathBuf>,
+}
+
+impl GitLsFiles {
+    pub fn new(git: impl AsRef<Path>, target_dir: impl AsRef<Path>) -> Result<Self> {
+        let git = Git::from_path(git)?;
+        let root_dir = git.get_rootdir(target_dir.as_ref())?;
+        Ok(Self {
+            inner: git,
+            root_dir,
+            index_file: None,
+        })
+    }
+
+    /// Lists this index file, such as one written by [`super::GitReadTree::read_into`], instead
+    /// of the index of the repository
+    pub fn with_index_file(mut self, index_file: Option<PathBuf>) -> Self {
+        self.index_file = index_file;
+        self
+    }
+
+    /// Returns the mode of every staged path, such as `100644`, `100755` (executable) or
+    /// `120000` (symlink)
+    pub fn modes(&self) -> Result<HashMap<String, String>> {
+        let mut command = self.inner.command(&self.root_dir);
+        command
+            .args(["ls-files", "--stage", "-z"])
+            .envs(self.index_file.iter().map(|x| ("GIT_INDEX_FILE", x)))
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let output = command.output()?;
+
+        if !output.status.success() {
+            return Err(Error::command(
+                "Failed to list the index",
+                &command,
+                &output,
+            ));
+        }
+
+        let stdout = String::from_utf8(output.stdout)?;
+        stdout
+            .split('\0')
+            .filter(|x| !x.is_empty())
+            .map(|entry| {
+                // <mode> <object> <stage>\t<path>
+                let (info, path) = entry
+                    .split_once('\t')
+                    .ok_or_else(|| Error::Parse(entry.to_string()))?;
+                let mode = info
+                    .split(' ')
+                    .next()
+                    .ok_or_else(|| Error::Parse(entry.to_string()))?;
+                Ok((path.to_string(), mode.to_string()))
+            })
+            .collect()
+    }
+}
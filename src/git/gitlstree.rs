@@ -1,7 +1,7 @@
 use super::Git;
 use super::{Error, Result};
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::Stdio;
 
 pub struct GitLsTree {
     inner: Git,
@@ -25,27 +25,28 @@ impl GitLsTree {
     }
 
     pub fn name_only(&self) -> Result<Vec<String>> {
-        self.inner.exec(&self.root_dir, |git| {
-            let args = vec!["ls-tree", "-r", "--name-only", &self.commit];
-            let output = Command::new(git)
-                .args(args)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .output()?;
-            let stdout = String::from_utf8(output.stdout)?;
-            let stderr = String::from_utf8(output.stderr)?;
+        let args = vec!["ls-tree", "-r", "--name-only", &self.commit];
+        let mut command = self.inner.command(&self.root_dir);
+        command
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let output = command.output()?;
 
-            if !output.status.success() {
-                return Err(Error::Command(format!(
-                    "Failed to get tree of files ({stderr})"
-                )));
-            }
+        if !output.status.success() {
+            return Err(Error::command(
+                "Failed to get tree of files",
+                &command,
+                &output,
+            ));
+        }
 
-            Ok(stdout
-                .split('\n')
-                .filter(|x| !x.is_empty())
-                .map(|x| x.into())
-                .collect::<Vec<_>>())
-        })
+        let stdout = String::from_utf8(output.stdout)?;
+
+        Ok(stdout
+            .split('\n')
+            .filter(|x| !x.is_empty())
+            .map(|x| x.into())
+            .collect::<Vec<_>>())
     }
 }
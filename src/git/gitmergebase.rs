@@ -1,7 +1,7 @@
 use super::Git;
 use super::{Error, Result};
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::Stdio;
 
 pub struct GitMergeBase {
     inner: Git,
@@ -19,24 +19,21 @@ impl GitMergeBase {
     }
 
     pub fn is_ancestor(&self, ancestor: &str, descendant: &str) -> Result<bool> {
-        self.inner.exec(&self.root_dir, |git| {
-            let output = Command::new(git)
-                .args(["merge-base", "--is-ancestor", ancestor, descendant])
-                .current_dir(&self.root_dir)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .output()?;
+        let mut command = self.inner.command(&self.root_dir);
+        command
+            .args(["merge-base", "--is-ancestor", ancestor, descendant])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let output = command.output()?;
 
-            match output.status.code() {
-                Some(0) => Ok(true),
-                Some(1) => Ok(false),
-                _ => {
-                    let stderr = String::from_utf8(output.stderr)?;
-                    Err(Error::Command(format!(
-                        "Failed to check merge-base ancestry ({stderr})"
-                    )))
-                }
-            }
-        })
+        match output.status.code() {
+            Some(0) => Ok(true),
+            Some(1) => Ok(false),
+            _ => Err(Error::command(
+                "Failed to check merge-base ancestry",
+                &command,
+                &output,
+            )),
+        }
     }
 }
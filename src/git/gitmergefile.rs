@@ -0,0 +1,64 @@
+use super::Git;
+use super::{Error, Result};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+/// The result of `git merge-file -p`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergedFile {
+    /// The merged contents, including conflict markers
+    pub contents: Vec<u8>,
+
+    /// The number of conflicts
+    pub conflicts: usize,
+}
+
+pub struct GitMergeFile {
+    inner: Git,
+    root_dir: PathBuf,
+}
+
+impl GitMergeFile {
+    pub fn new(git: impl AsRef<Path>, target_dir: impl AsRef<Path>) -> Result<Self> {
+        let git = Git::from_path(git)?;
+        let root_dir = git.get_rootdir(target_dir.as_ref())?;
+        Ok(Self {
+            inner: git,
+            root_dir,
+        })
+    }
+
+    /// Merges the changes from `base` to `theirs` into `ours`.
+    /// `labels` are the conflict marker labels for ours, base and theirs.
+    pub fn merge(
+        &self,
+        ours: &Path,
+        base: &Path,
+        theirs: &Path,
+        labels: [&str; 3],
+    ) -> Result<MergedFile> {
+        let mut command = self.inner.command(&self.root_dir);
+        command
+            .args(["merge-file", "-p"])
+            .args(labels.iter().flat_map(|x| ["-L", x]))
+            .arg(ours)
+            .arg(base)
+            .arg(theirs)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let output = command.output()?;
+
+        // The exit status is the number of conflicts, or negative on error
+        match output.status.code() {
+            Some(code) if (0..=127).contains(&code) => Ok(MergedFile {
+                contents: output.stdout,
+                conflicts: code as usize,
+            }),
+            _ => Err(Error::command(
+                format!("Failed to merge {}", ours.display()),
+                &command,
+                &output,
+            )),
+        }
+    }
+}
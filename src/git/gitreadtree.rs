@@ -0,0 +1,42 @@
+use super::Git;
+use super::{Error, Result};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+pub struct GitReadTree {
+    inner: Git,
+    root_dir: PathBuf,
+}
+
+impl GitReadTree {
+    pub fn new(git: impl AsRef<Path>, target_dir: impl AsRef<Path>) -> Result<Self> {
+        let git = Git::from_path(git)?;
+        let root_dir = git.get_rootdir(target_dir.as_ref())?;
+        Ok(Self {
+            inner: git,
+            root_dir,
+        })
+    }
+
+    /// Reads the tree of `rev` into `index_file` instead of the index of the repository
+    pub fn read_into(&self, rev: impl AsRef<str>, index_file: impl AsRef<Path>) -> Result<()> {
+        let rev = rev.as_ref();
+        let mut command = self.inner.command(&self.root_dir);
+        command
+            .args(["read-tree", rev])
+            .env("GIT_INDEX_FILE", index_file.as_ref())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let output = command.output()?;
+
+        if !output.status.success() {
+            return Err(Error::command(
+                format!("Failed to read the tree of {rev}"),
+                &command,
+                &output,
+            ));
+        }
+
+        Ok(())
+    }
+}
@@ -1,7 +1,7 @@
 use super::Git;
 use super::{Error, Result};
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::Stdio;
 
 pub struct GitReset {
     inner: Git,
@@ -25,19 +25,19 @@ impl GitReset {
     }
 
     pub fn hard(&self) -> Result<()> {
-        self.inner.exec(&self.root_dir, |git| {
-            let args = vec!["reset", "--hard", &self.commit];
-            let output = Command::new(git)
-                .args(args)
-                .stderr(Stdio::piped())
-                .output()?;
-            let stderr = String::from_utf8(output.stderr)?;
+        let args = vec!["reset", "--hard", &self.commit];
+        let mut command = self.inner.command(&self.root_dir);
+        command.args(args).stderr(Stdio::piped());
+        let output = command.output()?;
 
-            if !output.status.success() {
-                return Err(Error::Command(stderr));
-            }
+        if !output.status.success() {
+            return Err(Error::command(
+                format!("Failed to reset to {}", self.commit),
+                &command,
+                &output,
+            ));
+        }
 
-            Ok(())
-        })
+        Ok(())
     }
 }
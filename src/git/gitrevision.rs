@@ -1,7 +1,7 @@
 use super::Git;
 use super::{Error, Result};
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::Stdio;
 
 pub struct GitRevision {
     inner: Git,
@@ -24,7 +24,7 @@ impl GitRevision {
             &format!("Failed to get commit timestamp for {commit}"),
         )?
         .parse::<i64>()
-        .map_err(|_| Error::Command(format!("Failed to parse commit timestamp for {commit}")))
+        .map_err(|_| Error::Parse(format!("commit timestamp for {commit}")))
     }
 
     pub fn short_hash(&self, commit: &str) -> Result<String> {
@@ -35,25 +35,22 @@ impl GitRevision {
     }
 
     fn read_single_value(&self, args: &[&str], message: &str) -> Result<String> {
-        self.inner.exec(&self.root_dir, |git| {
-            let output = Command::new(git)
-                .args(args)
-                .current_dir(&self.root_dir)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .output()?;
+        let mut command = self.inner.command(&self.root_dir);
+        command
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let output = command.output()?;
 
-            if !output.status.success() {
-                let stderr = String::from_utf8(output.stderr)?;
-                return Err(Error::Command(format!("{message} ({stderr})")));
-            }
+        if !output.status.success() {
+            return Err(Error::command(message, &command, &output));
+        }
 
-            let stdout = String::from_utf8(output.stdout)?;
-            stdout
-                .lines()
-                .next()
-                .map(|line| line.to_string())
-                .ok_or_else(|| Error::Command(message.to_string()))
-        })
+        let stdout = String::from_utf8(output.stdout)?;
+        stdout
+            .lines()
+            .next()
+            .map(|line| line.to_string())
+            .ok_or_else(|| Error::Parse(stdout.clone()))
     }
 }
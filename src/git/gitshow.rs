@@ -0,0 +1,146 @@
+use super::Git;
+use super::{Error, FileChange, Result};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+/// `--format` of `git show` read by [`CommitDetail::parse`]
+const FORMAT: &str = "--format=%H%x00%P%x00%an <%ae>%x00%aI%x00%cn <%ce>%x00%cI%x00%B";
+
+/// Everything about a single commit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitDetail {
+    pub hash: String,
+    pub parents: Vec<String>,
+
+    /// Name and e-mail address of the author
+    pub author: String,
+
+    /// Author date in strict ISO 8601 format
+    pub author_date: String,
+
+    /// Name and e-mail address of the committer
+    pub committer: String,
+
+    /// Committer date in strict ISO 8601 format
+    pub committer_date: String,
+
+    /// The full commit message
+    pub message: String,
+
+    /// Files changed from the first parent (every file of a root commit)
+    pub files: Vec<FileChange>,
+}
+
+impl CommitDetail {
+    /// Parse from the output of `git show -s` with [`FORMAT`]. `files` is left empty.
+    fn parse(s: &str) -> Result<Self> {
+        let fields = s.splitn(7, '\0').collect::<Vec<_>>();
+        let [hash, parents, author, author_date, committer, committer_date, message] = fields[..]
+        else {
+            return Err(Error::Parse(s.to_string()));
+        };
+        Ok(Self {
+            hash: hash.trim().to_string(),
+            parents: parents.split_whitespace().map(Into::into).collect(),
+            author: author.into(),
+            author_date: author_date.into(),
+            committer: committer.into(),
+            committer_date: committer_date.into(),
+            message: message.trim_end().to_string(),
+            files: Vec::new(),
+        })
+    }
+}
+
+pub struct GitShow {
+    inner: Git,
+    root_dir: PathBuf,
+}
+
+impl GitShow {
+    pub fn new(git: impl AsRef<Path>, target_dir: impl AsRef<Path>) -> Result<Self> {
+        let git = Git::from_path(git)?;
+        let root_dir = git.get_rootdir(target_dir.as_ref())?;
+        Ok(Self {
+            inner: git,
+            root_dir,
+        })
+    }
+
+    pub fn commit(&self, rev: &str) -> Result<CommitDetail> {
+        let mut command = self.inner.command(&self.root_dir);
+        command
+            .args(["show", "-s", "--no-show-signature", FORMAT, rev])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let output = command.output()?;
+
+        if !output.status.success() {
+            return Err(Error::command(
+                format!("Failed to show {rev}"),
+                &command,
+                &output,
+            ));
+        }
+
+        let stdout = String::from_utf8(output.stdout)?;
+        let mut detail = CommitDetail::parse(&stdout)?;
+        detail.files = self.files(&detail)?;
+        Ok(detail)
+    }
+
+    fn files(&self, detail: &CommitDetail) -> Result<Vec<FileChange>> {
+        let mut args = vec![
+            "diff-tree",
+            "-r",
+            "-z",
+            "--name-status",
+            "-M",
+            "--no-commit-id",
+        ];
+        match detail.parents.first() {
+            Some(parent) => args.push(parent),
+            None => args.push("--root"),
+        }
+        args.push(&detail.hash);
+        let mut command = self.inner.command(&self.root_dir);
+        command
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let output = command.output()?;
+
+        if !output.status.success() {
+            return Err(Error::command(
+                format!("Failed to list the files of {}", detail.hash),
+                &command,
+                &output,
+            ));
+        }
+
+        let stdout = String::from_utf8(output.stdout)?;
+
+        FileChange::parse_all(&stdout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commit_detail() {
+        let detail = CommitDetail::parse(
+            "3706c44\0e252a0a 1d2c3b4\0A <a@example.com>\x002023-08-15T12:52:59+09:00\0\
+             B <b@example.com>\x002023-08-16T01:00:00+09:00\0Merge\n\nBody\0with nul\n\n",
+        )
+        .unwrap();
+        assert_eq!("3706c44", detail.hash);
+        assert_eq!(vec!["e252a0a", "1d2c3b4"], detail.parents);
+        assert_eq!("A <a@example.com>", detail.author);
+        assert_eq!("2023-08-16T01:00:00+09:00", detail.committer_date);
+        assert_eq!("Merge\n\nBody\0with nul", detail.message);
+
+        assert!(CommitDetail::parse("3706c44\0").is_err());
+    }
+}
@@ -1,7 +1,19 @@
 use super::Git;
-use super::{Error, Result};
+use super::{CommandFailure, Error, Result};
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::Stdio;
+use std::sync::{Mutex, MutexGuard};
+
+/// `git worktree` reads the admin files of every worktree of the repository and fails while
+/// another one is half created, so adding and removing worktrees is done one at a time
+static WORKTREE_LOCK: Mutex<()> = Mutex::new(());
+
+fn lock_worktrees() -> MutexGuard<'static, ()> {
+    match WORKTREE_LOCK.lock() {
+        Ok(lock) => lock,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
 
 pub struct GitWorktree {
     inner: Git,
@@ -25,20 +37,24 @@ impl GitWorktree {
     ) -> Result<()> {
         let worktree_dir = worktree_dir.as_ref();
         let commit = commit.as_ref();
-        let output = Command::new(&self.inner.path)
+        let _lock = lock_worktrees();
+        let mut command = self.inner.command(&self.root_dir);
+        command
             .arg("worktree")
             .arg("add")
             .arg("--detach")
             .arg(worktree_dir)
             .arg(commit)
-            .current_dir(&self.root_dir)
             .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .output()?;
+            .stderr(Stdio::piped());
+        let output = command.output()?;
 
         if !output.status.success() {
-            let stderr = String::from_utf8(output.stderr)?;
-            return Err(Error::Command(stderr));
+            return Err(Error::WorktreeAdd {
+                rev: commit.into(),
+                path: worktree_dir.into(),
+                failure: CommandFailure::new(&command, &output),
+            });
         }
 
         Ok(())
@@ -46,19 +62,26 @@ impl GitWorktree {
 
     pub fn remove_force(&self, worktree_dir: impl AsRef<Path>) -> Result<()> {
         let worktree_dir = worktree_dir.as_ref();
-        let output = Command::new(&self.inner.path)
+        let _lock = lock_worktrees();
+        let mut command = self.inner.command(&self.root_dir);
+        command
             .arg("worktree")
             .arg("remove")
             .arg("--force")
             .arg(worktree_dir)
-            .current_dir(&self.root_dir)
             .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .output()?;
+            .stderr(Stdio::piped());
+        let output = command.output()?;
 
         if !output.status.success() {
-            let stderr = String::from_utf8(output.stderr)?;
-            return Err(Error::Command(stderr));
+            return Err(Error::command(
+                format!(
+                    "Failed to remove the worktree at {}",
+                    worktree_dir.display()
+                ),
+                &command,
+                &output,
+            ));
         }
 
         Ok(())
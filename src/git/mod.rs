@@ -1,28 +1,40 @@
 mod error;
+mod gitcatfile;
 mod gitcheckout;
+mod gitcheckoutindex;
 mod gitdiff;
 mod gitlocalbranches;
 mod gitlog;
+mod gitlsfiles;
 mod gitlstree;
 mod gitmergebase;
+mod gitmergefile;
+mod gitreadtree;
 mod gitreset;
 mod gitrevision;
+mod gitshow;
 mod gitworktree;
 mod onelinelog;
 
-pub use error::{Error, Result};
+pub use error::{CommandFailure, Error, Result};
+pub use gitcatfile::{Blob, GitCatFile};
 pub use gitcheckout::GitCheckout;
-pub use gitdiff::GitDiff;
-pub use gitlocalbranches::{GitLocalBranch, GitLocalBranches};
-pub use gitlog::GitLog;
+pub use gitcheckoutindex::GitCheckoutIndex;
+pub use gitdiff::{FileChange, GitDiff};
+pub use gitlocalbranches::{GitLocalBranch, GitLocalBranches, RefSource};
+pub use gitlog::{GitLog, LogLines};
+pub use gitlsfiles::GitLsFiles;
 pub use gitlstree::GitLsTree;
 pub use gitmergebase::GitMergeBase;
+pub use gitmergefile::{GitMergeFile, MergedFile};
+pub use gitreadtree::GitReadTree;
 pub use gitreset::GitReset;
 pub use gitrevision::GitRevision;
+pub use gitshow::{CommitDetail, GitShow};
 pub use gitworktree::GitWorktree;
 pub use onelinelog::{Commit, OnelineLog};
 
-use std::env::{self, current_dir};
+use std::io;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
@@ -33,18 +45,26 @@ pub struct Git {
 
 impl Git {
     pub fn get_version(path: impl AsRef<Path>) -> Result<String> {
-        let output = Command::new(path.as_ref())
+        let path = path.as_ref();
+        let mut command = Command::new(path);
+        untranslated(&mut command)
             .arg("--version")
             .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .output()?;
+            .stderr(Stdio::piped());
+        let output = command.output().map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => Error::GitNotFound { path: path.into() },
+            // Such as a git that is not executable
+            _ => Error::Io(io::Error::new(
+                e.kind(),
+                format!("Failed to run {} ({e})", path.display()),
+            )),
+        })?;
+
+        if !output.status.success() || !output.stderr.is_empty() {
+            return Err(Error::command("Failed to get version", &command, &output));
+        }
 
         let stdout = String::from_utf8(output.stdout)?;
-        let stderr = String::from_utf8(output.stderr)?;
-
-        if !stderr.is_empty() {
-            return Err(Error::Command("Failed to get version".into()));
-        }
 
         if let Some(ret) = stdout.split('\n').next() {
             let s = "git version ";
@@ -57,7 +77,7 @@ impl Git {
                 Ok(ret.into())
             }
         } else {
-            Err(Error::Command("Failed to get version".into()))
+            Err(Error::Parse(stdout))
         }
     }
 
@@ -70,15 +90,12 @@ impl Git {
         })
     }
 
-    pub(super) fn exec<R, F: FnOnce(&PathBuf) -> Result<R>>(
-        &self,
-        target_dir: impl AsRef<Path>,
-        f: F,
-    ) -> Result<R> {
-        let dir = Self::change_currentdir(target_dir)?;
-        let ret = f(&self.path);
-        Self::change_currentdir(dir)?;
-        ret
+    /// Creates a git command that runs in `dir`.
+    /// The process-wide current directory is never changed, so `Git` can be used from any thread.
+    pub(super) fn command(&self, dir: impl AsRef<Path>) -> Command {
+        let mut command = Command::new(&self.path);
+        untranslated(&mut command).current_dir(dir);
+        command
     }
 
     pub fn version(&self) -> &str {
@@ -86,61 +103,89 @@ impl Git {
     }
 
     pub fn get_rootdir(&self, path: impl AsRef<Path>) -> Result<PathBuf> {
-        self.exec(path, |git| {
-            let output = Command::new(git)
-                .arg("rev-parse")
-                .arg("--show-superproject-working-tree")
-                .arg("--show-toplevel")
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .output()?;
-            let stdout = String::from_utf8(output.stdout)?;
-
-            if !output.status.success() {
-                let stderr = String::from_utf8(output.stderr)?;
-                return Err(Error::Command(format!(
-                    "Failed to get root directory ({stderr})"
-                )));
-            }
+        let mut command = self.command(path);
+        command
+            .arg("rev-parse")
+            .arg("--show-superproject-working-tree")
+            .arg("--show-toplevel")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let output = command.output()?;
+
+        if !output.status.success() {
+            return Err(Error::command(
+                "Failed to get root directory",
+                &command,
+                &output,
+            ));
+        }
 
-            if let Some(ret) = stdout.split('\n').next() {
-                Ok(ret.into())
-            } else {
-                Err(Error::Command("Failed to get root directory".into()))
-            }
-        })
+        let stdout = String::from_utf8(output.stdout)?;
+
+        if let Some(ret) = stdout.split('\n').next() {
+            Ok(ret.into())
+        } else {
+            Err(Error::Parse(stdout))
+        }
     }
 
     pub fn get_hash(&self, path: impl AsRef<Path>, commit: impl AsRef<str>) -> Result<String> {
-        self.exec(path, |git| {
-            let commit = commit.as_ref();
-            let output = Command::new(git)
-                .arg("rev-parse")
-                .arg(commit)
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .output()?;
-
-            let stdout = String::from_utf8(output.stdout)?;
-
-            if !output.status.success() {
-                let stderr = String::from_utf8(output.stderr)?;
-                return Err(Error::Command(format!(
-                    "Failed to get hash of {commit} ({stderr})"
-                )));
-            }
+        let commit = commit.as_ref();
+        let mut command = self.command(path);
+        command
+            .arg("rev-parse")
+            .arg(commit)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let output = command.output()?;
+
+        if !output.status.success() {
+            return Err(
+                match Error::command(format!("Failed to get hash of {commit}"), &command, &output) {
+                    Error::UnknownRevision { failure, .. } => Error::UnknownRevision {
+                        rev: commit.into(),
+                        failure,
+                    },
+                    e => e,
+                },
+            );
+        }
 
-            if let Some(ret) = stdout.split('\n').next() {
-                Ok(ret.into())
-            } else {
-                Err(Error::Command(format!("Failed to get hash of {commit}")))
-            }
-        })
+        let stdout = String::from_utf8(output.stdout)?;
+
+        if let Some(ret) = stdout.split('\n').next() {
+            Ok(ret.into())
+        } else {
+            Err(Error::Parse(stdout))
+        }
     }
+}
+
+/// Makes git print its messages in English, as [`Error::command`] classifies failures by them
+fn untranslated(command: &mut Command) -> &mut Command {
+    command.env("LC_ALL", "C").env_remove("LANGUAGE")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failures_are_classified_under_a_translated_locale() {
+        let not_a_repo = std::env::temp_dir().join(format!("gde-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&not_a_repo).unwrap();
+        let git = Git::from_path("git").unwrap();
+        let mut command = git.command(&not_a_repo);
+        // Git translates its messages where the German locale is installed
+        command
+            .arg("rev-parse")
+            .env("LANG", "de_DE.UTF-8")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let output = command.output().unwrap();
+        let _ = std::fs::remove_dir_all(&not_a_repo);
 
-    fn change_currentdir(to: impl AsRef<Path>) -> Result<PathBuf> {
-        let dir = current_dir()?;
-        env::set_current_dir(to)?;
-        Ok(dir)
+        let e = Error::command("Failed", &command, &output);
+        assert!(matches!(e, Error::NotARepository { .. }), "{e}");
     }
 }
@@ -94,6 +94,28 @@ impl Commit {
     pub fn message(&self) -> &str {
         &self.message
     }
+
+    /// Branches, tags and so on pointing to this commit
+    pub fn aliases(&self) -> Option<&str> {
+        self.aliases.as_deref()
+    }
+
+    pub fn date(&self) -> &str {
+        &self.date
+    }
+
+    pub fn author(&self) -> &str {
+        &self.author
+    }
+
+    /// This commit without the graph columns in front of its `*`, for a list that hides commits
+    pub fn without_graph(&self) -> Self {
+        Self {
+            tree_head: String::new(),
+            hash_padding: String::new(),
+            ..self.clone()
+        }
+    }
 }
 
 impl Display for Commit {
@@ -1,6 +1,20 @@
+pub mod archive;
 mod auto;
+pub mod checksum;
 mod copy;
+pub mod drift;
+mod error;
+pub mod event;
 pub mod git;
+pub mod manifest;
+pub mod merge;
+pub mod pathspec;
+pub mod plan;
+pub mod report;
+pub mod script;
+
+use std::fs;
+use std::path::Path;
 
 pub struct Defer<F: FnOnce()> {
     f: Option<F>,
@@ -24,18 +38,40 @@ impl<F: FnOnce()> Drop for Defer<F> {
     }
 }
 
-pub use auto::AutoCopy;
-pub use copy::FilesCopy;
-
-#[cfg(test)]
-pub(crate) mod testutil {
-    use std::sync::{Mutex, MutexGuard, OnceLock};
+/// Creates the output directory, reporting a failure as [`Error::OutputDirNotWritable`]
+pub(crate) fn create_output_dir(dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir).map_err(|source| Error::OutputDirNotWritable {
+        path: dir.to_path_buf(),
+        source,
+    })
+}
 
-    pub(crate) fn git_test_lock() -> MutexGuard<'static, ()> {
-        static LOCK: OnceLock<Mutex<()>> = OnceLock::new();
-        match LOCK.get_or_init(|| Mutex::new(())).lock() {
-            Ok(lock) => lock,
-            Err(poisoned) => poisoned.into_inner(),
+/// Returns every file under `dir` as a sorted, `/`-separated path relative to `dir`
+pub(crate) fn list_files(dir: &Path) -> anyhow::Result<Vec<String>> {
+    fn list(dir: &Path, prefix: &str, files: &mut Vec<String>) -> anyhow::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let name = name
+                .to_str()
+                .ok_or_else(|| anyhow::anyhow!("Non UTF-8 file name in {}", dir.display()))?;
+            let path = format!("{prefix}{name}");
+            if entry.file_type()?.is_dir() {
+                list(&entry.path(), &format!("{path}/"), files)?;
+            } else {
+                files.push(path);
+            }
         }
+
+        Ok(())
     }
+
+    let mut files = Vec::new();
+    list(dir, "", &mut files)?;
+    files.sort();
+    Ok(files)
 }
+
+pub use auto::AutoCopy;
+pub use copy::{CopyOptions, Extraction, FilesCopy, INDEX, WORKTREE};
+pub use error::{Error, Result};
@@ -0,0 +1,202 @@
+use crate::git::FileChange;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File name of the JSON manifest written to the output directory
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// File name of the list of files the receiving side must remove
+pub const DELETED_FILE: &str = "deleted.txt";
+
+/// How a file changed between the two commits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeStatus {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+    Copied,
+    TypeChanged,
+}
+
+/// A single file in the manifest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub status: ChangeStatus,
+
+    /// The path of the file (the new path for renamed files)
+    pub path: String,
+
+    /// The previous path of a renamed file or the source of a copied file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_path: Option<String>,
+
+    /// Similarity index in percent of a renamed or copied file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub similarity: Option<u8>,
+}
+
+impl ManifestEntry {
+    /// The path of the file at the "from" commit, if it exists there
+    pub fn path_at_from(&self) -> Option<&str> {
+        match self.status {
+            ChangeStatus::Added | ChangeStatus::Copied => None,
+            ChangeStatus::Modified | ChangeStatus::Deleted | ChangeStatus::TypeChanged => {
+                Some(&self.path)
+            }
+            ChangeStatus::Renamed => self.from_path.as_deref(),
+        }
+    }
+
+    /// The path of the file at the "to" commit, if it exists there
+    pub fn path_at_to(&self) -> Option<&str> {
+        match self.status {
+            ChangeStatus::Deleted => None,
+            _ => Some(&self.path),
+        }
+    }
+}
+
+impl From<&FileChange> for ManifestEntry {
+    fn from(value: &FileChange) -> Self {
+        let (status, path, from_path, similarity) = match value {
+            FileChange::Added(path) => (ChangeStatus::Added, path, None, None),
+            FileChange::Modified(path) => (ChangeStatus::Modified, path, None, None),
+            FileChange::Deleted(path) => (ChangeStatus::Deleted, path, None, None),
+            FileChange::TypeChanged(path) => (ChangeStatus::TypeChanged, path, None, None),
+            FileChange::Renamed {
+                from,
+                to,
+                similarity,
+            } => (ChangeStatus::Renamed, to, Some(from), Some(*similarity)),
+            FileChange::Copied {
+                from,
+                to,
+                similarity,
+            } => (ChangeStatus::Copied, to, Some(from), Some(*similarity)),
+        };
+        Self {
+            status,
+            path: path.clone(),
+            from_path: from_path.cloned(),
+            similarity,
+        }
+    }
+}
+
+/// Machine-readable description of the differences between two commits
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// The "from" commit
+    pub from: String,
+
+    /// The "to" commit
+    pub to: String,
+
+    /// Every changed file
+    pub files: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    pub fn new(from: impl Into<String>, to: impl Into<String>, changes: &[FileChange]) -> Self {
+        Self {
+            from: from.into(),
+            to: to.into(),
+            files: changes.iter().map(ManifestEntry::from).collect(),
+        }
+    }
+
+    /// Reads `manifest.json` from the output directory
+    pub fn read(output_dir: impl AsRef<Path>) -> Result<Self> {
+        let s = fs::read_to_string(output_dir.as_ref().join(MANIFEST_FILE))?;
+        Ok(serde_json::from_str(&s)?)
+    }
+
+    /// Paths that no longer exist at the "to" commit and must be removed by the receiving side
+    pub fn deleted(&self) -> Vec<&str> {
+        self.files
+            .iter()
+            .filter_map(|x| match x.status {
+                ChangeStatus::Deleted => Some(x.path.as_str()),
+                ChangeStatus::Renamed => x.from_path.as_deref(),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Writes `manifest.json` and `deleted.txt` to the output directory and returns their paths
+    pub fn write(&self, output_dir: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+        let output_dir = output_dir.as_ref();
+        let manifest_file = output_dir.join(MANIFEST_FILE);
+        fs::write(&manifest_file, serde_json::to_string_pretty(self)? + "\n")?;
+
+        let deleted_file = output_dir.join(DELETED_FILE);
+        let deleted = self
+            .deleted()
+            .into_iter()
+            .map(|x| format!("{x}\n"))
+            .collect::<String>();
+        fs::write(&deleted_file, deleted)?;
+
+        Ok(vec![manifest_file, deleted_file])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest_lists_deleted_and_renamed_sources() {
+        let manifest = Manifest::new(
+            "a",
+            "b",
+            &[
+                FileChange::Added("added.txt".to_string()),
+                FileChange::Modified("changed.txt".to_string()),
+                FileChange::Deleted("deleted.txt".to_string()),
+                FileChange::Renamed {
+                    from: "old.txt".to_string(),
+                    to: "new.txt".to_string(),
+                    similarity: 90,
+                },
+            ],
+        );
+
+        assert_eq!(vec!["deleted.txt", "old.txt"], manifest.deleted());
+        assert_eq!(Some("old.txt"), manifest.files[3].from_path.as_deref());
+        assert_eq!("new.txt", manifest.files[3].path);
+        assert_eq!(Some(90), manifest.files[3].similarity);
+    }
+
+    #[test]
+    fn manifest_round_trips_through_json() {
+        let manifest = Manifest::new(
+            "a",
+            "b",
+            &[
+                FileChange::Deleted("deleted.txt".to_string()),
+                FileChange::TypeChanged("link".to_string()),
+                FileChange::Copied {
+                    from: "src.txt".to_string(),
+                    to: "copy.txt".to_string(),
+                    similarity: 100,
+                },
+                FileChange::Renamed {
+                    from: "old.txt".to_string(),
+                    to: "new.txt".to_string(),
+                    similarity: 90,
+                },
+            ],
+        );
+
+        let json = serde_json::to_string(&manifest).unwrap();
+        assert!(json.contains(r#""status":"deleted""#));
+        assert!(json.contains(r#""status":"type_changed""#));
+        assert!(!json.contains(r#""path":"deleted.txt","from_path""#));
+        assert_eq!(manifest, serde_json::from_str::<Manifest>(&json).unwrap());
+    }
+}
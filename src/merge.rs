@@ -0,0 +1,242 @@
+use crate::git::GitMergeFile;
+use crate::manifest::Manifest;
+use crate::Defer;
+use anyhow::Result;
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory in the output directory holding the merged files
+pub const MERGED_DIR: &str = "merged";
+
+/// File name of the conflict summary written to the output directory
+pub const MERGE_SUMMARY_FILE: &str = "merge-summary.txt";
+
+/// The outcome of merging a file the target has changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeStatus {
+    /// Merged without conflicts
+    Clean,
+
+    /// Merged with this number of conflicts marked in the file
+    Conflicted(usize),
+
+    /// Not merged because one of the versions is binary
+    Binary,
+
+    /// Not merged because the file is deleted at the "to" commit
+    Deleted,
+
+    /// Not merged because the "from" or "to" side is not a regular file, such as a submodule,
+    /// a dangling symlink or the other side of a type change
+    NotAFile,
+}
+
+/// A file that differs between the target and the "from" commit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeEntry {
+    /// The path of the file at the "to" commit (the "from" path for deleted files)
+    pub path: String,
+
+    pub status: MergeStatus,
+}
+
+impl Display for MergeEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.status {
+            MergeStatus::Clean => write!(f, "clean: {}", self.path),
+            MergeStatus::Conflicted(1) => write!(f, "conflict: {} (1 conflict)", self.path),
+            MergeStatus::Conflicted(n) => write!(f, "conflict: {} ({n} conflicts)", self.path),
+            MergeStatus::Binary => write!(f, "binary: {} (not merged)", self.path),
+            MergeStatus::Deleted => write!(f, "deleted: {} (changed in the target)", self.path),
+            MergeStatus::NotAFile => write!(f, "not a file: {} (not merged)", self.path),
+        }
+    }
+}
+
+/// Every file merged by [`merge_target`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergeSummary {
+    pub entries: Vec<MergeEntry>,
+}
+
+impl MergeSummary {
+    /// Files that need manual resolution
+    pub fn unresolved(&self) -> Vec<&MergeEntry> {
+        self.entries
+            .iter()
+            .filter(|x| x.status != MergeStatus::Clean)
+            .collect()
+    }
+
+    /// Writes `merge-summary.txt` to the output directory and returns its path
+    pub fn write(&self, output_dir: impl AsRef<Path>) -> Result<PathBuf> {
+        let summary_file = output_dir.as_ref().join(MERGE_SUMMARY_FILE);
+        let s = self
+            .entries
+            .iter()
+            .map(|x| format!("{x}\n"))
+            .collect::<String>();
+        fs::write(&summary_file, s)?;
+        Ok(summary_file)
+    }
+}
+
+/// Three-way merges every file the target has changed since the "from" commit.
+/// The base is the file in `from/`, ours is the file in `to/` and theirs is the file in
+/// `target_dir`. Merged files, with conflict markers, are written to `merged/`.
+/// Files the package adds that already exist in the target are merged against an empty base.
+pub fn merge_target(
+    git_path: impl AsRef<Path>,
+    repo_dir: impl AsRef<Path>,
+    output_dir: impl AsRef<Path>,
+    manifest: &Manifest,
+    target_dir: impl AsRef<Path>,
+) -> Result<MergeSummary> {
+    let output_dir = output_dir.as_ref();
+    let merge_file = GitMergeFile::new(git_path, repo_dir)?;
+    let merged_dir = output_dir.join(MERGED_DIR);
+
+    let empty_file = std::env::temp_dir().join(format!("gde-empty-{}", uuid::Uuid::new_v4()));
+    fs::write(&empty_file, b"")?;
+    let _empty_guard = Defer::new(|| {
+        let _ = fs::remove_file(&empty_file);
+    });
+
+    let mut summary = MergeSummary::default();
+    for change in changed_in_target(output_dir, manifest, target_dir.as_ref())? {
+        let (path, base, ours, theirs) = match change {
+            TargetChange::Merge {
+                path,
+                base,
+                ours,
+                theirs,
+            } => (
+                path,
+                base.unwrap_or_else(|| empty_file.clone()),
+                ours,
+                theirs,
+            ),
+            TargetChange::Skip(entry) => {
+                summary.entries.push(entry);
+                continue;
+            }
+        };
+        let merged = merge_file.merge(
+            &ours,
+            &base,
+            &theirs,
+            [&manifest.to, &manifest.from, "target"],
+        )?;
+        let dest_file = merged_dir.join(&path);
+        if let Some(dir) = dest_file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&dest_file, &merged.contents)?;
+        let status = match merged.conflicts {
+            0 => MergeStatus::Clean,
+            n => MergeStatus::Conflicted(n),
+        };
+        summary.entries.push(MergeEntry { path, status });
+    }
+
+    Ok(summary)
+}
+
+/// The paths [`merge_target`] writes under `merged/`, found without merging or writing anything
+pub fn files_to_merge(
+    output_dir: impl AsRef<Path>,
+    manifest: &Manifest,
+    target_dir: impl AsRef<Path>,
+) -> Result<Vec<String>> {
+    Ok(
+        changed_in_target(output_dir.as_ref(), manifest, target_dir.as_ref())?
+            .into_iter()
+            .filter_map(|x| match x {
+                TargetChange::Merge { path, .. } => Some(path),
+                TargetChange::Skip(_) => None,
+            })
+            .collect(),
+    )
+}
+
+/// What [`merge_target`] does with a file the target has changed since the "from" commit
+enum TargetChange {
+    /// Three-way merged into `merged/<path>` (`base` is `None` for a file the package adds)
+    Merge {
+        path: String,
+        base: Option<PathBuf>,
+        ours: PathBuf,
+        theirs: PathBuf,
+    },
+
+    /// Not merged and reported as is
+    Skip(MergeEntry),
+}
+
+/// Compares the target with the "from" files and decides what to do with each changed file
+fn changed_in_target(
+    output_dir: &Path,
+    manifest: &Manifest,
+    target_dir: &Path,
+) -> Result<Vec<TargetChange>> {
+    let from_dir = output_dir.join("from");
+    let to_dir = output_dir.join("to");
+
+    let mut changes = Vec::new();
+    for entry in manifest.files.iter() {
+        let (base, theirs) = match entry.path_at_from() {
+            Some(path) => (Some(from_dir.join(path)), target_dir.join(path)),
+            None => (None, target_dir.join(&entry.path)),
+        };
+        if !theirs.is_file() {
+            continue;
+        }
+        let ours = entry.path_at_to().map(|path| to_dir.join(path));
+        if base.iter().chain(ours.iter()).any(|x| !x.is_file()) {
+            changes.push(TargetChange::Skip(MergeEntry {
+                path: entry.path.clone(),
+                status: MergeStatus::NotAFile,
+            }));
+            continue;
+        }
+        let theirs_bytes = fs::read(&theirs)?;
+        let base_bytes = match base.as_ref() {
+            Some(base) => fs::read(base)?,
+            None => Vec::new(),
+        };
+        if theirs_bytes == base_bytes {
+            continue;
+        }
+
+        let (Some(path), Some(ours)) = (entry.path_at_to(), ours) else {
+            changes.push(TargetChange::Skip(MergeEntry {
+                path: entry.path.clone(),
+                status: MergeStatus::Deleted,
+            }));
+            continue;
+        };
+        if [fs::read(&ours)?, base_bytes, theirs_bytes]
+            .iter()
+            .any(|x| is_binary(x))
+        {
+            changes.push(TargetChange::Skip(MergeEntry {
+                path: path.to_string(),
+                status: MergeStatus::Binary,
+            }));
+        } else {
+            changes.push(TargetChange::Merge {
+                path: path.to_string(),
+                base,
+                ours,
+                theirs,
+            });
+        }
+    }
+    Ok(changes)
+}
+
+/// Same heuristic as git: a NUL byte in the first 8000 bytes
+fn is_binary(bytes: &[u8]) -> bool {
+    bytes.iter().take(8000).any(|&x| x == 0)
+}
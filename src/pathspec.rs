@@ -0,0 +1,84 @@
+use anyhow::Result;
+use std::fs;
+use std::path::Path;
+
+/// File name of the checked-in list of globs to exclude, read from the repository root
+pub const IGNORE_FILE: &str = ".gdeignore";
+
+/// Builds the git pathspecs that limit which changed files are packaged.
+/// `include` and `exclude` are globs relative to the repository root (`*` does not match `/`,
+/// `**` matches any number of directories, and a directory matches everything below it).
+/// `raw` pathspecs are passed to git as they are. The globs in `.gdeignore` are excluded too.
+pub fn build(
+    root_dir: impl AsRef<Path>,
+    include: &[String],
+    exclude: &[String],
+    raw: &[String],
+) -> Result<Vec<String>> {
+    let mut ret = Vec::new();
+    ret.extend(include.iter().map(|x| format!(":(glob){x}")));
+    ret.extend(raw.iter().cloned());
+    ret.extend(exclude.iter().map(|x| format!(":(exclude,glob){x}")));
+    ret.extend(
+        read_ignore_file(root_dir)?
+            .into_iter()
+            .map(|x| format!(":(exclude,glob){x}")),
+    );
+    Ok(ret)
+}
+
+/// Reads the globs in `.gdeignore`, skipping blank lines and `#` comments
+fn read_ignore_file(root_dir: impl AsRef<Path>) -> Result<Vec<String>> {
+    let ignore_file = root_dir.as_ref().join(IGNORE_FILE);
+    if !ignore_file.is_file() {
+        return Ok(Vec::new());
+    }
+
+    Ok(fs::read_to_string(ignore_file)?
+        .lines()
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty() && !x.starts_with('#'))
+        .map(|x| x.trim_start_matches('/').trim_end_matches('/').to_string())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use outdir_tempdir::TempDir;
+
+    #[test]
+    fn build_converts_globs_and_reads_ignore_file() {
+        let dir = TempDir::new().autorm();
+        fs::write(
+            dir.path().join(IGNORE_FILE),
+            "# never shipped\n\n/docs/\n.github\n  **/*.test.js  \n",
+        )
+        .unwrap();
+
+        let pathspecs = build(
+            dir.path(),
+            &["src/**".to_string()],
+            &["*.md".to_string()],
+            &[":(icase)readme".to_string()],
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                ":(glob)src/**",
+                ":(icase)readme",
+                ":(exclude,glob)*.md",
+                ":(exclude,glob)docs",
+                ":(exclude,glob).github",
+                ":(exclude,glob)**/*.test.js",
+            ],
+            pathspecs
+        );
+    }
+
+    #[test]
+    fn build_returns_nothing_without_filters() {
+        let dir = TempDir::new().autorm();
+        assert!(build(dir.path(), &[], &[], &[]).unwrap().is_empty());
+    }
+}
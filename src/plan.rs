@@ -0,0 +1,101 @@
+use crate::manifest::ManifestEntry;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// What [`crate::FilesCopy::copy`] would do, computed without writing anything
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CopyPlan {
+    /// The resolved "from" commit
+    pub from: String,
+
+    /// The resolved "to" commit, or `WORKTREE` / `INDEX`
+    pub to: String,
+
+    /// The output directory
+    pub output_dir: PathBuf,
+
+    /// Every changed file
+    pub files: Vec<ManifestEntry>,
+
+    /// Every file that would be written, in the order they are written
+    pub writes: Vec<PathBuf>,
+}
+
+impl CopyPlan {
+    pub fn write_text<W: Write>(&self, w: &mut W) -> Result<()> {
+        writeln!(w, "From: {}", self.from)?;
+        writeln!(w, "To: {}", self.to)?;
+        writeln!(w, "Output directory: {}", self.output_dir.display())?;
+        writeln!(w, "Changed file count: {}", self.files.len())?;
+        for path in self.writes.iter() {
+            writeln!(w, "Would write: {}", path.display())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A branch [`crate::AutoCopy`] would process
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BranchPlan {
+    pub name: String,
+    pub head: String,
+    pub plan: CopyPlan,
+}
+
+/// A branch [`crate::AutoCopy`] does not process and why
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedBranch {
+    pub name: String,
+    pub reason: String,
+}
+
+/// A branch [`crate::AutoCopy::selection`] lists
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BranchCandidate {
+    pub name: String,
+    pub head: String,
+
+    /// The abbreviated head, used in output directory names
+    pub short_head: String,
+
+    /// Why the selection skips the branch, or `None` if it is in range
+    pub reason: Option<String>,
+
+    /// Where the branch would be copied, or `None` if it is not processed
+    pub output_dir: Option<PathBuf>,
+}
+
+/// What [`crate::AutoCopy::copy`] would do, computed without writing anything
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AutoPlan {
+    /// The resolved base commit
+    pub base: String,
+
+    pub branches: Vec<BranchPlan>,
+
+    pub skipped: Vec<SkippedBranch>,
+}
+
+impl AutoPlan {
+    pub fn write_text<W: Write>(&self, w: &mut W) -> Result<()> {
+        writeln!(w, "Base commit: {}", self.base)?;
+        for branch in self.skipped.iter() {
+            writeln!(w, "Skipped branch: {} ({})", branch.name, branch.reason)?;
+        }
+        writeln!(w, "Selected branch count: {}", self.branches.len())?;
+        for branch in self.branches.iter() {
+            writeln!(
+                w,
+                "Planned branch: {} -> {}",
+                branch.name,
+                branch.plan.output_dir.display()
+            )?;
+            branch.plan.write_text(w)?;
+        }
+
+        Ok(())
+    }
+}
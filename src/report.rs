@@ -0,0 +1,183 @@
+use crate::checksum;
+use crate::manifest::ManifestEntry;
+use crate::plan::SkippedBranch;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// File name of the JSON report written to the output directory
+pub const REPORT_FILE: &str = "report.json";
+
+/// Size and digest of a written file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileDigest {
+    pub size: u64,
+    pub sha256: String,
+}
+
+impl FileDigest {
+    /// Reads `path`, or returns `None` if nothing was written there
+    pub fn of(path: impl AsRef<Path>) -> Result<Option<Self>> {
+        let path = path.as_ref();
+        if !path.is_file() {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            size: fs::metadata(path)?.len(),
+            sha256: checksum::sha256_file(path)?,
+        }))
+    }
+}
+
+/// A changed file and what was written for it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileReport {
+    #[serde(flatten)]
+    pub entry: ManifestEntry,
+
+    /// The file written to `from/`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<FileDigest>,
+
+    /// The file written to `to/`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<FileDigest>,
+}
+
+/// How long a step of a run took
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Timing {
+    pub step: String,
+    pub duration_ms: u64,
+}
+
+/// Record of a [`crate::FilesCopy::copy`] run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CopyReport {
+    pub git_version: String,
+
+    /// The resolved "from" commit
+    pub from: String,
+
+    /// The resolved "to" commit, or `WORKTREE` / `INDEX`
+    pub to: String,
+
+    pub output_dir: PathBuf,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive: Option<PathBuf>,
+
+    /// Unix time the run started at
+    pub started_at: u64,
+
+    pub duration_ms: u64,
+
+    pub timings: Vec<Timing>,
+
+    pub files: Vec<FileReport>,
+}
+
+impl CopyReport {
+    /// Writes the report to `path`
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(())
+    }
+}
+
+/// A branch processed by [`crate::AutoCopy`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BranchReport {
+    pub name: String,
+    pub head: String,
+    pub output_dir: PathBuf,
+
+    /// Why the branch failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report: Option<CopyReport>,
+}
+
+/// Record of a [`crate::AutoCopy::run`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AutoReport {
+    pub git_version: String,
+
+    /// The resolved base commit
+    pub base: String,
+
+    pub output_dir: PathBuf,
+
+    /// Unix time the run started at
+    pub started_at: u64,
+
+    pub duration_ms: u64,
+
+    pub branches: Vec<BranchReport>,
+
+    pub skipped: Vec<SkippedBranch>,
+}
+
+impl AutoReport {
+    /// Branches that failed
+    pub fn failed(&self) -> Vec<&BranchReport> {
+        self.branches.iter().filter(|x| x.error.is_some()).collect()
+    }
+
+    /// Writes the report to `report.json` in the output directory and returns its path
+    pub fn write(&self) -> Result<PathBuf> {
+        fs::create_dir_all(&self.output_dir)?;
+        let report_file = self.output_dir.join(REPORT_FILE);
+        fs::write(&report_file, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(report_file)
+    }
+}
+
+/// Measures the steps of a run
+pub(crate) struct Stopwatch {
+    started_at: u64,
+    start: Instant,
+    last: Instant,
+    timings: Vec<Timing>,
+}
+
+impl Stopwatch {
+    pub(crate) fn start() -> Self {
+        let now = Instant::now();
+        Self {
+            started_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|x| x.as_secs())
+                .unwrap_or_default(),
+            start: now,
+            last: now,
+            timings: Vec::new(),
+        }
+    }
+
+    pub(crate) fn started_at(&self) -> u64 {
+        self.started_at
+    }
+
+    /// Records the time since the previous step as `step`
+    pub(crate) fn lap(&mut self, step: &str) {
+        let now = Instant::now();
+        self.timings.push(Timing {
+            step: step.to_string(),
+            duration_ms: (now - self.last).as_millis() as u64,
+        });
+        self.last = now;
+    }
+
+    pub(crate) fn elapsed_ms(&self) -> u64 {
+        self.start.elapsed().as_millis() as u64
+    }
+
+    pub(crate) fn timings(&self) -> Vec<Timing> {
+        self.timings.clone()
+    }
+}
@@ -0,0 +1,345 @@
+use crate::manifest::Manifest;
+use anyhow::Result;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What a generated script checks and changes in the target directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptPlan {
+    /// The directory in the package holding the state the target must be in (e.g. "from")
+    pub expected_dir: String,
+
+    /// The directory in the package holding the files to copy over the target (e.g. "to")
+    pub source_dir: String,
+
+    /// Files that must exist in the target with the same contents as in `expected_dir`
+    pub unchanged: Vec<String>,
+
+    /// Files that must not exist in the target yet
+    pub absent: Vec<String>,
+
+    /// Files to remove from the target
+    pub remove: Vec<String>,
+
+    /// Files to copy from `source_dir` over the target
+    pub copy: Vec<String>,
+}
+
+impl ScriptPlan {
+    /// The plan that turns a tree in the "from" state into the "to" state
+    pub fn apply(manifest: &Manifest) -> Self {
+        let from = manifest
+            .files
+            .iter()
+            .filter_map(|x| x.path_at_from())
+            .collect::<BTreeSet<_>>();
+        let to = manifest
+            .files
+            .iter()
+            .filter_map(|x| x.path_at_to())
+            .collect::<BTreeSet<_>>();
+        Self::between("from", &from, "to", &to)
+    }
+
+    /// The plan that undoes [`ScriptPlan::apply`]: turns a tree in the "to" state back into
+    /// the "from" state, re-creating deleted files and removing added ones
+    pub fn rollback(manifest: &Manifest) -> Self {
+        let apply = Self::apply(manifest);
+        let from = apply.unchanged.iter().map(|x| x.as_str()).collect();
+        let to = apply.copy.iter().map(|x| x.as_str()).collect();
+        Self::between("to", &to, "from", &from)
+    }
+
+    fn between(
+        expected_dir: &str,
+        expected: &BTreeSet<&str>,
+        source_dir: &str,
+        source: &BTreeSet<&str>,
+    ) -> Self {
+        let to_strings = |x: Vec<&&str>| x.into_iter().map(|x| x.to_string()).collect();
+        Self {
+            expected_dir: expected_dir.to_string(),
+            source_dir: source_dir.to_string(),
+            unchanged: to_strings(expected.iter().collect()),
+            absent: to_strings(source.difference(expected).collect()),
+            remove: to_strings(expected.difference(source).collect()),
+            copy: to_strings(source.iter().collect()),
+        }
+    }
+
+    /// Generates a POSIX shell script that takes the target directory as its only argument
+    pub fn posix(&self) -> String {
+        let mut s = String::new();
+        s.push_str("#!/bin/sh\n");
+        s.push_str(&format!(
+            "# Generated by gde. Copies {}/ over the target directory.\n",
+            self.source_dir
+        ));
+        s.push_str(
+            r#"set -eu
+
+if [ $# -ne 1 ]; then
+    echo "Usage: $0 <TARGET DIR>" >&2
+    exit 2
+fi
+
+package_dir=$(cd "$(dirname "$0")" && pwd)
+target_dir=$1
+failed=0
+
+expect_unchanged() {
+"#,
+        );
+        s.push_str(&format!(
+            r#"    if [ ! -f "$target_dir/$1" ]; then
+        echo "Missing: $1" >&2
+        failed=1
+    elif ! cmp -s "$package_dir/{}/$1" "$target_dir/$1"; then
+        echo "Modified: $1" >&2
+        failed=1
+    fi
+}}
+
+expect_absent() {{
+    if [ -e "$target_dir/$1" ]; then
+        echo "Already exists: $1" >&2
+        failed=1
+    fi
+}}
+
+remove_file() {{
+    rm -f "$target_dir/$1"
+    echo "Removed: $1"
+}}
+
+copy_file() {{
+    mkdir -p "$(dirname "$target_dir/$1")"
+    cp "$package_dir/{}/$1" "$target_dir/$1"
+    echo "Copied: $1"
+}}
+
+"#,
+            self.expected_dir, self.source_dir
+        ));
+        for path in self.unchanged.iter() {
+            s.push_str(&format!("expect_unchanged {}\n", sh_quote(path)));
+        }
+        for path in self.absent.iter() {
+            s.push_str(&format!("expect_absent {}\n", sh_quote(path)));
+        }
+        s.push_str(&format!(
+            r#"
+if [ "$failed" -ne 0 ]; then
+    echo "The target directory does not match {}/; nothing was changed" >&2
+    exit 1
+fi
+
+"#,
+            self.expected_dir
+        ));
+        for path in self.remove.iter() {
+            s.push_str(&format!("remove_file {}\n", sh_quote(path)));
+        }
+        for path in self.copy.iter() {
+            s.push_str(&format!("copy_file {}\n", sh_quote(path)));
+        }
+        s
+    }
+
+    /// Generates a PowerShell script that takes the target directory as `-Target`
+    pub fn powershell(&self) -> String {
+        let mut s = String::new();
+        s.push_str(&format!(
+            "# Generated by gde. Copies {}/ over the target directory.\n",
+            self.source_dir
+        ));
+        s.push_str(
+            r#"param(
+    [Parameter(Mandatory = $true)]
+    [string]$Target
+)
+
+$ErrorActionPreference = 'Stop'
+$PackageDir = $PSScriptRoot
+$Failed = $false
+
+"#,
+        );
+        s.push_str(&format!(
+            r#"function Test-Unchanged([string]$Path) {{
+    $expected = Join-Path (Join-Path $PackageDir {}) $Path
+    $actual = Join-Path $Target $Path
+    if (-not (Test-Path -LiteralPath $actual -PathType Leaf)) {{
+        Write-Host "Missing: $Path"
+        $script:Failed = $true
+    }} elseif ((Get-FileHash -LiteralPath $expected).Hash -ne (Get-FileHash -LiteralPath $actual).Hash) {{
+        Write-Host "Modified: $Path"
+        $script:Failed = $true
+    }}
+}}
+
+function Test-Absent([string]$Path) {{
+    if (Test-Path -LiteralPath (Join-Path $Target $Path)) {{
+        Write-Host "Already exists: $Path"
+        $script:Failed = $true
+    }}
+}}
+
+function Remove-PackageFile([string]$Path) {{
+    Remove-Item -LiteralPath (Join-Path $Target $Path) -Force -ErrorAction SilentlyContinue
+    Write-Host "Removed: $Path"
+}}
+
+function Copy-PackageFile([string]$Path) {{
+    $source = Join-Path (Join-Path $PackageDir {}) $Path
+    $dest = Join-Path $Target $Path
+    New-Item -ItemType Directory -Force -Path (Split-Path -Parent $dest) | Out-Null
+    Copy-Item -LiteralPath $source -Destination $dest -Force
+    Write-Host "Copied: $Path"
+}}
+
+"#,
+            ps_quote(&self.expected_dir),
+            ps_quote(&self.source_dir)
+        ));
+        for path in self.unchanged.iter() {
+            s.push_str(&format!("Test-Unchanged {}\n", ps_quote(path)));
+        }
+        for path in self.absent.iter() {
+            s.push_str(&format!("Test-Absent {}\n", ps_quote(path)));
+        }
+        s.push_str(&format!(
+            r#"
+if ($Failed) {{
+    Write-Host 'The target directory does not match {}/; nothing was changed'
+    exit 1
+}}
+
+"#,
+            self.expected_dir.replace('\'', "''")
+        ));
+        for path in self.remove.iter() {
+            s.push_str(&format!("Remove-PackageFile {}\n", ps_quote(path)));
+        }
+        for path in self.copy.iter() {
+            s.push_str(&format!("Copy-PackageFile {}\n", ps_quote(path)));
+        }
+        s
+    }
+
+    /// Writes the POSIX shell and PowerShell scripts as `<name>.sh` and `<name>.ps1`
+    pub fn write(&self, output_dir: impl AsRef<Path>, name: &str) -> Result<Vec<PathBuf>> {
+        let output_dir = output_dir.as_ref();
+        let sh_file = output_dir.join(format!("{name}.sh"));
+        fs::write(&sh_file, self.posix())?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&sh_file, fs::Permissions::from_mode(0o755))?;
+        }
+
+        let ps1_file = output_dir.join(format!("{name}.ps1"));
+        fs::write(&ps1_file, self.powershell())?;
+        Ok(vec![sh_file, ps1_file])
+    }
+}
+
+/// Quotes `s` as a single word for POSIX shells
+fn sh_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Quotes `s` as a verbatim PowerShell string
+fn ps_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::FileChange;
+
+    fn manifest() -> Manifest {
+        Manifest::new(
+            "a",
+            "b",
+            &[
+                FileChange::Added("added.txt".to_string()),
+                FileChange::Modified("it's.txt".to_string()),
+                FileChange::Deleted("deleted.txt".to_string()),
+                FileChange::Renamed {
+                    from: "old.txt".to_string(),
+                    to: "new.txt".to_string(),
+                    similarity: 100,
+                },
+            ],
+        )
+    }
+
+    #[test]
+    fn apply_plan_checks_from_state_and_copies_to_files() {
+        let plan = ScriptPlan::apply(&manifest());
+
+        assert_eq!("from", plan.expected_dir);
+        assert_eq!("to", plan.source_dir);
+        assert_eq!(vec!["deleted.txt", "it's.txt", "old.txt"], plan.unchanged);
+        assert_eq!(vec!["added.txt", "new.txt"], plan.absent);
+        assert_eq!(vec!["deleted.txt", "old.txt"], plan.remove);
+        assert_eq!(vec!["added.txt", "it's.txt", "new.txt"], plan.copy);
+    }
+
+    #[test]
+    fn rollback_plan_is_the_inverse_of_apply() {
+        let plan = ScriptPlan::rollback(&manifest());
+
+        assert_eq!("to", plan.expected_dir);
+        assert_eq!("from", plan.source_dir);
+        assert_eq!(vec!["added.txt", "it's.txt", "new.txt"], plan.unchanged);
+        assert_eq!(vec!["deleted.txt", "old.txt"], plan.absent);
+        assert_eq!(vec!["added.txt", "new.txt"], plan.remove);
+        assert_eq!(vec!["deleted.txt", "it's.txt", "old.txt"], plan.copy);
+        assert!(plan
+            .posix()
+            .contains(r#"cp "$package_dir/from/$1" "$target_dir/$1""#));
+    }
+
+    #[test]
+    fn posix_script_quotes_paths() {
+        let script = ScriptPlan::apply(&manifest()).posix();
+
+        assert!(script.starts_with("#!/bin/sh\n"));
+        assert!(script.contains(r#"cmp -s "$package_dir/from/$1" "$target_dir/$1""#));
+        assert!(script.contains(r#"cp "$package_dir/to/$1" "$target_dir/$1""#));
+        assert!(script.contains("expect_unchanged 'it'\\''s.txt'\n"));
+        assert!(script.contains("expect_absent 'new.txt'\n"));
+        assert!(script.contains("remove_file 'old.txt'\n"));
+        assert!(script.contains("copy_file 'added.txt'\n"));
+    }
+
+    #[test]
+    fn posix_script_checks_before_changing_anything() {
+        let script = ScriptPlan::apply(&manifest()).posix();
+
+        let check = script.find("expect_absent 'added.txt'").unwrap();
+        let abort = script.find(r#"if [ "$failed" -ne 0 ]"#).unwrap();
+        let remove = script.find("remove_file 'deleted.txt'").unwrap();
+        let copy = script.find("copy_file 'added.txt'").unwrap();
+        assert!(check < abort);
+        assert!(abort < remove);
+        assert!(remove < copy);
+    }
+
+    #[test]
+    fn powershell_script_quotes_paths() {
+        let script = ScriptPlan::apply(&manifest()).powershell();
+
+        assert!(script.contains("[string]$Target"));
+        assert!(script.contains("Join-Path $PackageDir 'from'"));
+        assert!(script.contains("Join-Path $PackageDir 'to'"));
+        assert!(script.contains("Test-Unchanged 'it''s.txt'\n"));
+        assert!(script.contains("Test-Absent 'new.txt'\n"));
+        assert!(script.contains("Remove-PackageFile 'old.txt'\n"));
+        assert!(script.contains("Copy-PackageFile 'added.txt'\n"));
+    }
+}